@@ -0,0 +1,97 @@
+//! Measures the parser throughput on a large synthetic norg document.
+//!
+//! Run it with `cargo bench -p norg_pandoc_ast`. The size of the corpus can be changed with the
+//! `NORG_BENCH_SECTIONS` environment variable, the number of runs with `NORG_BENCH_RUNS`
+
+use std::{
+    env,
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use norg_pandoc_ast::ParseOptions;
+
+fn main() {
+    let sections = env_or("NORG_BENCH_SECTIONS", 2000);
+    let runs = env_or("NORG_BENCH_RUNS", 10);
+
+    let corpus = corpus(sections);
+    let options = ParseOptions::new();
+    let megabytes = corpus.len() as f64 / 1_000_000.0;
+    println!("corpus: {sections} sections, {megabytes:.2} MB");
+
+    let mut total = Duration::ZERO;
+    let mut fastest = Duration::MAX;
+    for _ in 0..runs {
+        let start = Instant::now();
+        let parsed = norg_pandoc_ast::parse(black_box(&corpus), &options)
+            .expect("the synthetic corpus should parse");
+        let elapsed = start.elapsed();
+        black_box(parsed);
+
+        total += elapsed;
+        fastest = fastest.min(elapsed);
+    }
+
+    let mean = total / runs as u32;
+    println!(
+        "mean: {mean:.2?} ({:.2} MB/s), fastest: {fastest:.2?} ({:.2} MB/s)",
+        megabytes / mean.as_secs_f64(),
+        megabytes / fastest.as_secs_f64()
+    );
+}
+
+fn env_or(name: &str, default: usize) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Builds a document that touches most of the supported syntax, so no single node kind
+/// dominates the measurement
+fn corpus(sections: usize) -> String {
+    let mut corpus =
+        String::from("@document.meta\ntitle: Benchmark\nlist-style: 1. a) i.\n@end\n\n");
+
+    for section in 0..sections {
+        writeln!(corpus, "* Section {section}").unwrap();
+        writeln!(
+            corpus,
+            "  Some *bold*, /italic/ and _underlined_ text with `inline code`, a link to\n  \
+             {{* Section {}}}[the previous section] and an <inline target {section}>.\n",
+            section.saturating_sub(1)
+        )
+        .unwrap();
+
+        writeln!(corpus, "** Lists").unwrap();
+        for item in 0..5 {
+            writeln!(
+                corpus,
+                "   - Item {item} with ^superscript^ and ,subscript,"
+            )
+            .unwrap();
+            writeln!(corpus, "   -- Nested item {item}").unwrap();
+            writeln!(corpus, "   ~~~ Deeply nested ordered item {item}").unwrap();
+        }
+        writeln!(corpus, "   - (!) Something urgent\n").unwrap();
+
+        writeln!(corpus, "** Quotes and code").unwrap();
+        writeln!(corpus, "   > A quote in section {section}").unwrap();
+        writeln!(
+            corpus,
+            "   >> With a nested quote and a {{# Section {section}}}\n"
+        )
+        .unwrap();
+        writeln!(
+            corpus,
+            "   @code rust\n   fn section_{section}() -> usize {{\n       {section}\n   }}\n   @end\n"
+        )
+        .unwrap();
+
+        writeln!(corpus, "   ___\n").unwrap();
+    }
+
+    corpus
+}
@@ -1,57 +1,45 @@
-use std::collections::LinkedList;
-
-use pandoc_ast::Block;
-
-use crate::{block, inline, Meta};
-
-pub(super) fn parse(parse_meta: &mut Meta) -> Block {
-    let nesting: i64 = {
-        let number_index = parse_meta
-            .tree
-            .node()
-            .kind()
-            .chars()
-            .position(|c| c.is_ascii_digit())
-            .unwrap();
-        parse_meta.tree.node().kind()[number_index..]
-            .parse()
-            .unwrap()
-    };
+use crate::{block, inline, norg::Heading, Meta, ParseError, Span};
 
-    if !parse_meta.tree.goto_first_child() || !parse_meta.tree.goto_next_sibling() {
-        unreachable!()
-    }
+pub(super) fn parse(parse_meta: &mut Meta) -> Result<Heading, ParseError> {
+    parse_meta.first_child()?;
+
+    let prefix = parse_meta.span();
+    let level = block::prefix_nesting(parse_meta, '*')?;
 
-    let (mut text, extension_length) =
-        if parse_meta.tree.node().kind() == "detached_modifier_extension" {
-            let item = inline::detached_extension::parse(parse_meta);
-            parse_meta.tree.goto_next_sibling();
-            let len = item.len();
-            (item, len)
-        } else {
-            (LinkedList::new(), 0)
-        };
-
-    let text: Vec<_> = if parse_meta.tree.goto_first_child() {
-        text.append(&mut inline::parse(parse_meta));
-        text.into_iter().collect()
+    parse_meta.next_sibling()?;
+
+    let extension = if parse_meta.tree.node().kind() == "detached_modifier_extension" {
+        let extension = inline::detached_extension::parse(parse_meta)?;
+        parse_meta.next_sibling()?;
+        Some(extension)
     } else {
-        unreachable!()
+        None
     };
 
-    let id = {
-        let mut id = inline::to_string(&text[extension_length..]);
-        id.push_str(&nesting.to_string());
-        id
+    let title_span = parse_meta.span();
+    let line_span = Span {
+        byte_range: prefix.byte_range.start..title_span.byte_range.end,
+        line: prefix.line,
+        column: prefix.column,
+        end_line: title_span.end_line,
+        end_column: title_span.end_column,
     };
-    let header = Block::Header(nesting, (id, vec![], vec![]), text);
-    let content = if parse_meta.tree.goto_next_sibling() {
-        let mut content = block::parse(parse_meta);
-        content.push_front(header);
-        content.into()
+
+    parse_meta.first_child()?;
+    let title = inline::collect(parse_meta)?;
+
+    let mut content = vec![];
+    if parse_meta.tree.goto_next_sibling() {
+        block::parse(parse_meta, &mut content)?;
     } else {
         parse_meta.tree.goto_parent();
-        vec![header]
-    };
-    Block::Div((String::default(), vec![], vec![]), content)
+    }
+
+    Ok(Heading {
+        level,
+        extension,
+        title,
+        line_span,
+        content,
+    })
 }
@@ -1,68 +1,115 @@
-use std::collections::VecDeque;
-
-use pandoc_ast::{Block, Inline};
-
-use crate::Meta;
+use crate::{
+    norg::{Block, BlockKind},
+    Diagnostic, Meta, ParseError,
+};
 
 mod heading;
-mod list;
+mod nestable;
 mod paragraph;
-mod quote;
 mod tags;
 
-pub(super) fn parse(parse_meta: &mut Meta) -> VecDeque<Block> {
-    let block = match parse_meta.tree.node().kind() {
-        "paragraph" => paragraph::parse(parse_meta),
+/// Parses the block under the cursor and all of its following siblings into `blocks`.
+///
+/// Afterwards the cursor is moved back up to the parent
+pub(super) fn parse(parse_meta: &mut Meta, blocks: &mut Vec<Block>) -> Result<(), ParseError> {
+    loop {
+        parse_block(parse_meta, blocks)?;
+
+        if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            return Ok(());
+        }
+    }
+}
+
+/// Counts the repetitions of `modifier` in the detached modifier prefix under the cursor.
+///
+/// Tree sitter stops distinguishing nesting levels after the 6th one, the prefix itself doesn't
+fn prefix_nesting(parse_meta: &Meta, modifier: char) -> Result<usize, ParseError> {
+    Ok(parse_meta
+        .text()?
+        .chars()
+        .filter(|c| *c == modifier)
+        .count())
+}
+
+/// Parses the block under the cursor into `blocks` without moving on to its siblings.
+///
+/// Most nodes produce exactly one block, but some produce none (e.g. delimiters) or several.
+/// If tree sitter couldn't parse parts of the block and the parser fails because of that, the
+/// block is kept as raw text and the error is turned into a warning. Blocks that were already
+/// pushed for the failed node are dropped again
+fn parse_block(parse_meta: &mut Meta, blocks: &mut Vec<Block>) -> Result<(), ParseError> {
+    let node = parse_meta.tree.node();
+    let len = blocks.len();
+
+    match parse_node(parse_meta, blocks) {
+        Err(error) if node.has_error() => {
+            // The error happened somewhere inside the node, so the cursor has to get back to it
+            while parse_meta.tree.node() != node {
+                if !parse_meta.tree.goto_parent() {
+                    return Err(error);
+                }
+            }
+
+            parse_meta.diagnostics.push(Diagnostic::recovered(error));
+            blocks.truncate(len);
+            blocks.push(Block {
+                kind: BlockKind::Error(parse_meta.text()?.to_owned()),
+                span: parse_meta.span(),
+            });
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn parse_node(parse_meta: &mut Meta, blocks: &mut Vec<Block>) -> Result<(), ParseError> {
+    let span = parse_meta.span();
+
+    let kind = match parse_meta.tree.node().kind() {
+        _ if parse_meta.tree.node().is_error() => {
+            parse_meta.warn("tree sitter couldn't parse this, keeping the raw text");
+            BlockKind::Error(parse_meta.text()?.to_owned())
+        }
+
+        kind if parse_meta.tree.node().is_missing() => {
+            parse_meta.warn(format!("missing {kind}"));
+            return Ok(());
+        }
+
+        "paragraph" => paragraph::parse(parse_meta)?,
 
-        "generic_list" => list::parse(parse_meta),
+        "generic_list" => BlockKind::List(nestable::parse(parse_meta)?),
 
-        "quote" => quote::parse(parse_meta),
+        "quote" => BlockKind::Quote(nestable::parse(parse_meta)?),
 
-        s if s.starts_with("heading") => heading::parse(parse_meta),
+        s if s.starts_with("heading") => BlockKind::Heading(heading::parse(parse_meta)?),
 
-        "ranged_verbatim_tag" => tags::verbatim::parse(parse_meta),
+        "ranged_verbatim_tag" => match tags::verbatim::parse(parse_meta)? {
+            Some(tag) => BlockKind::RangedTag(tag),
+            None => return Ok(()),
+        },
 
         "strong_paragraph_delimiter"
         | "weak_paragraph_delimiter"
         | "_line_break"
-        | "_paragraph_break" => {
-            return if parse_meta.tree.goto_next_sibling() {
-                parse(parse_meta)
-            } else {
-                parse_meta.tree.goto_parent();
-                VecDeque::default()
-            }
-        }
+        | "_paragraph_break" => return Ok(()),
 
-        "horizontal_line" => Block::HorizontalRule,
+        "horizontal_line" => BlockKind::HorizontalRule,
 
-        "infirm_tag" => tags::infirm::parse(parse_meta),
+        "infirm_tag" => BlockKind::InfirmTag(tags::infirm::parse(parse_meta)?),
+
+        "strong_carryover_set" | "weak_carryover_set" => {
+            return tags::carryover::parse(parse_meta, blocks)
+        }
 
         _ => {
-            eprintln!("{} not implemented", parse_meta.tree.node().kind());
-            Block::Plain(vec![Inline::Str(
-                parse_meta
-                    .tree
-                    .node()
-                    .utf8_text(parse_meta.source)
-                    .unwrap()
-                    .to_owned(),
-            )])
+            parse_meta.warn("not supported yet, keeping the raw text");
+            BlockKind::Unsupported(parse_meta.text()?.to_owned())
         }
     };
 
-    if parse_meta.tree.goto_next_sibling() {
-        let mut next_blocks = parse(parse_meta);
-        if !matches!(block, Block::Null) {
-            next_blocks.push_front(block);
-        }
-        next_blocks
-    } else {
-        parse_meta.tree.goto_parent();
-        if !matches!(block, Block::Null) {
-            VecDeque::from([block])
-        } else {
-            VecDeque::default()
-        }
-    }
+    blocks.push(Block { kind, span });
+    Ok(())
 }
@@ -0,0 +1,109 @@
+use crate::{
+    block, inline,
+    norg::{Block, Nestable, NestableKind},
+    Meta, ParseError,
+};
+
+/// Parses the items of a `generic_list` or a `quote` in document order
+pub(super) fn parse(parse_meta: &mut Meta) -> Result<Vec<Nestable>, ParseError> {
+    let is_item = if parse_meta.tree.node().kind() == "quote" {
+        is_quote
+    } else {
+        is_list_item
+    };
+
+    parse_meta.first_child()?;
+
+    let mut items = vec![];
+    parse_items(parse_meta, is_item, &mut items)?;
+
+    Ok(items)
+}
+
+/// Collects the item under the cursor, its nested items and all following items
+fn parse_items(
+    parse_meta: &mut Meta,
+    is_item: fn(&str) -> bool,
+    items: &mut Vec<Nestable>,
+) -> Result<(), ParseError> {
+    loop {
+        let (kind, modifier) = match parse_meta.tree.node().kind() {
+            s if s.starts_with("unordered_list") => (NestableKind::Unordered, '-'),
+            s if s.starts_with("ordered_list") => (NestableKind::Ordered, '~'),
+            s if s.starts_with("quote") => (NestableKind::Quote, '>'),
+            _ => return Err(parse_meta.error("expected a list or quote item")),
+        };
+        let span = parse_meta.span();
+
+        parse_meta.first_child()?;
+
+        let level = block::prefix_nesting(parse_meta, modifier)?;
+
+        let mut extension = None;
+        let (content, has_nested_items) = if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            (vec![], false)
+        } else if parse_meta.tree.node().kind() == "detached_modifier_extension" {
+            extension = Some(inline::detached_extension::parse(parse_meta)?);
+            if parse_meta.tree.goto_next_sibling() {
+                parse_content(parse_meta, is_item)?
+            } else {
+                parse_meta.tree.goto_parent();
+                (vec![], false)
+            }
+        } else {
+            parse_content(parse_meta, is_item)?
+        };
+
+        items.push(Nestable {
+            kind,
+            level,
+            extension,
+            content,
+            span,
+        });
+
+        if has_nested_items {
+            parse_items(parse_meta, is_item, items)?;
+        }
+
+        if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            return Ok(());
+        }
+    }
+}
+
+/// Parses the content of an item, starting at the cursor, until the first nested item or the
+/// end of the item.
+///
+/// Returns the content and whether the cursor stopped on a nested item. If it didn't, the cursor
+/// is moved back up to the item itself
+fn parse_content(
+    parse_meta: &mut Meta,
+    is_item: fn(&str) -> bool,
+) -> Result<(Vec<Block>, bool), ParseError> {
+    let mut content = vec![];
+    let has_nested_items = loop {
+        if is_item(parse_meta.tree.node().kind()) {
+            break true;
+        }
+
+        block::parse_block(parse_meta, &mut content)?;
+
+        if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            break false;
+        }
+    };
+
+    Ok((content, has_nested_items))
+}
+
+fn is_list_item(kind: &str) -> bool {
+    kind.starts_with("unordered_list") || kind.starts_with("ordered_list")
+}
+
+fn is_quote(kind: &str) -> bool {
+    kind.starts_with("quote")
+}
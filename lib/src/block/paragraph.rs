@@ -1,13 +1,11 @@
-use pandoc_ast::Block;
+use crate::{inline, norg::BlockKind, Meta, ParseError};
 
-use crate::{inline, Meta};
-
-pub(super) fn parse(parse_meta: &mut Meta) -> Block {
+pub(super) fn parse(parse_meta: &mut Meta) -> Result<BlockKind, ParseError> {
     let inline = if parse_meta.tree.goto_first_child() {
-        inline::parse(parse_meta).into_iter().collect()
+        inline::collect(parse_meta)?
     } else {
         vec![]
     };
 
-    Block::Para(inline)
+    Ok(BlockKind::Paragraph(inline))
 }
@@ -0,0 +1,74 @@
+use std::mem;
+
+use crate::{
+    block,
+    norg::{Block, BlockKind, Tag},
+    Meta, ParseError,
+};
+
+/// Parses a strong or weak carryover set.
+///
+/// Every block in the set gets the tags in front of it
+pub(in crate::block) fn parse(
+    parse_meta: &mut Meta,
+    blocks: &mut Vec<Block>,
+) -> Result<(), ParseError> {
+    let span = parse_meta.span();
+
+    parse_meta.first_child()?;
+
+    let mut tags = vec![];
+    loop {
+        if parse_meta.tree.node().kind().ends_with("_carryover") {
+            tags.push(parse_tag(parse_meta)?);
+        } else {
+            let mut targets = vec![];
+            block::parse_block(parse_meta, &mut targets)?;
+            for target in targets {
+                if tags.is_empty() {
+                    blocks.push(target);
+                } else {
+                    blocks.push(Block {
+                        kind: BlockKind::Carryover(mem::take(&mut tags), Box::new(target)),
+                        span: span.clone(),
+                    });
+                }
+            }
+        }
+
+        if !parse_meta.tree.goto_next_sibling() {
+            break;
+        }
+    }
+
+    parse_meta.tree.goto_parent();
+
+    Ok(())
+}
+
+fn parse_tag(parse_meta: &mut Meta) -> Result<Tag, ParseError> {
+    let mut tag = Tag {
+        name: String::new(),
+        parameters: vec![],
+        content: String::new(),
+        span: parse_meta.span(),
+    };
+
+    parse_meta.first_child()?;
+
+    loop {
+        match parse_meta.tree.node().kind() {
+            "tag_name" => tag.name = parse_meta.text()?.to_owned(),
+            "tag_parameters" => tag.parameters = super::parse_parameters(parse_meta)?,
+            _ => {}
+        }
+
+        if !parse_meta.tree.goto_next_sibling() {
+            break;
+        }
+    }
+
+    parse_meta.tree.goto_parent();
+
+    Ok(tag)
+}
@@ -1,32 +1,28 @@
-use pandoc_ast::{Block, Inline};
+use crate::{norg::Tag, Meta, ParseError};
 
-use crate::Meta;
+pub(in crate::block) fn parse(parse_meta: &mut Meta) -> Result<Tag, ParseError> {
+    let span = parse_meta.span();
 
-pub(in crate::block) fn parse(parse_meta: &mut Meta) -> Block {
-    if !parse_meta.tree.goto_first_child() || !parse_meta.tree.goto_next_sibling() {
-        unreachable!()
-    }
+    parse_meta.first_child()?;
+    parse_meta.next_sibling()?;
 
-    if parse_meta.tree.node().utf8_text(parse_meta.source).unwrap() == "image" {
-        if !parse_meta.tree.goto_next_sibling() || !parse_meta.tree.goto_next_sibling() {
-            unreachable!()
-        }
+    let name = parse_meta.text()?.to_owned();
 
-        let target = parse_meta
-            .tree
-            .node()
-            .utf8_text(parse_meta.source)
-            .unwrap()
-            .to_owned();
+    let mut parameters = vec![];
+    while parse_meta.tree.goto_next_sibling() {
+        match parse_meta.tree.node().kind() {
+            "tag_parameters" => parameters = super::parse_parameters(parse_meta)?,
+            kind if !kind.starts_with('_') => parameters.push(parse_meta.text()?.to_owned()),
+            _ => {}
+        }
+    }
 
-        parse_meta.tree.goto_parent();
+    parse_meta.tree.goto_parent();
 
-        Block::Plain(vec![Inline::Image(
-            (String::new(), vec![], vec![]),
-            vec![],
-            (target, String::default()),
-        )])
-    } else {
-        unimplemented!()
-    }
+    Ok(Tag {
+        name,
+        parameters,
+        content: String::new(),
+        span,
+    })
 }
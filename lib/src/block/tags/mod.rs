@@ -1,2 +1,24 @@
+use crate::{Meta, ParseError};
+
+pub(super) mod carryover;
 pub(super) mod infirm;
 pub(super) mod verbatim;
+
+/// Collects the `tag_param`s of the `tag_parameters` node under the cursor
+fn parse_parameters(parse_meta: &mut Meta) -> Result<Vec<String>, ParseError> {
+    parse_meta.first_child()?;
+
+    let mut parameters = vec![];
+    loop {
+        if parse_meta.tree.node().kind() == "tag_param" {
+            parameters.push(parse_meta.text()?.to_owned());
+        }
+        if !parse_meta.tree.goto_next_sibling() {
+            break;
+        }
+    }
+
+    parse_meta.tree.goto_parent();
+
+    Ok(parameters)
+}
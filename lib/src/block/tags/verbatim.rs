@@ -1,27 +1,20 @@
-use std::{
-    io::Write,
-    process::{Command, Stdio},
-};
+use crate::{norg::Tag, Meta, ParseError};
 
-use pandoc_ast::{Block, MetaValue};
+/// Parses a ranged verbatim tag.
+///
+/// The `@document.meta` tag is stored in the metadata instead, so there is no tag for it
+pub(in crate::block) fn parse(parse_meta: &mut Meta) -> Result<Option<Tag>, ParseError> {
+    let span = parse_meta.span();
 
-use crate::Meta;
+    parse_meta.first_child()?;
+    parse_meta.next_sibling()?;
 
-pub(in crate::block) fn parse(parse_meta: &mut Meta) -> Block {
-    if !parse_meta.tree.goto_first_child() || !parse_meta.tree.goto_next_sibling() {
-        unreachable!()
+    let name = parse_meta.text()?.to_owned();
+    if name == "document.meta" {
+        meta(parse_meta)?;
+        return Ok(None);
     }
 
-    if parse_meta.tree.node().utf8_text(parse_meta.source).unwrap() == "code" {
-        code(parse_meta)
-    } else if parse_meta.tree.node().utf8_text(parse_meta.source).unwrap() == "document.meta" {
-        meta(parse_meta)
-    } else {
-        general(parse_meta)
-    }
-}
-
-pub(in crate::block) fn code(parse_meta: &mut Meta) -> Block {
     loop {
         if !parse_meta.tree.goto_next_sibling() || parse_meta.tree.node().kind() != "_space" {
             break;
@@ -29,77 +22,47 @@ pub(in crate::block) fn code(parse_meta: &mut Meta) -> Block {
     }
 
     let parameters = if parse_meta.tree.node().kind() == "tag_parameters" {
-        if !parse_meta.tree.goto_first_child() {
-            unreachable!()
-        }
-
-        let mut classes = vec![];
-        loop {
-            if parse_meta.tree.node().kind() == "tag_param" {
-                classes.push(
-                    parse_meta
-                        .tree
-                        .node()
-                        .utf8_text(parse_meta.source)
-                        .unwrap()
-                        .to_owned(),
-                );
-            }
-            if !parse_meta.tree.goto_next_sibling() {
-                break;
-            }
-        }
-
-        parse_meta.tree.goto_parent();
-
-        (String::new(), classes, vec![])
+        super::parse_parameters(parse_meta)?
     } else {
-        (String::new(), vec![], vec![])
+        vec![]
     };
 
-    while parse_meta.tree.node().kind() != "ranged_verbatim_tag_content" {
-        if !parse_meta.tree.goto_next_sibling() {
-            unreachable!()
-        }
-    }
+    goto_content(parse_meta)?;
 
-    let content = parse_meta.tree.node().utf8_text(parse_meta.source).unwrap();
+    let content = parse_meta.text()?.to_owned();
 
     parse_meta.tree.goto_parent();
 
-    Block::CodeBlock(parameters, content.to_owned())
+    Ok(Some(Tag {
+        name,
+        parameters,
+        content,
+        span,
+    }))
 }
 
-pub(in crate::block) fn meta(parse_meta: &mut Meta) -> Block {
+/// Moves the cursor forward to the content of the tag
+fn goto_content(parse_meta: &mut Meta) -> Result<(), ParseError> {
     while parse_meta.tree.node().kind() != "ranged_verbatim_tag_content" {
-        if !parse_meta.tree.goto_next_sibling() {
-            unreachable!()
-        }
+        parse_meta.next_sibling()?;
     }
+    Ok(())
+}
+
+fn meta(parse_meta: &mut Meta) -> Result<(), ParseError> {
+    goto_content(parse_meta)?;
 
     if parse_meta.tree.goto_first_child() {
         if parse_meta.tree.goto_first_child() {
             loop {
                 if parse_meta.tree.node().kind() == "paragraph_segment" {
-                    let mut key_value = parse_meta
-                        .tree
-                        .node()
-                        .utf8_text(parse_meta.source)
-                        .unwrap()
-                        .split(':');
-                    let key = if let Some(key) = key_value.next() {
-                        if key == "authors" {
-                            String::from("author")
-                        } else {
-                            key.to_owned()
-                        }
-                    } else {
-                        unreachable!()
+                    let mut key_value = parse_meta.text()?.split(':');
+                    let key = match key_value.next() {
+                        Some(key) => key.to_owned(),
+                        None => return Err(parse_meta.error("expected a metadata key")),
                     };
                     let value = key_value.collect::<String>().trim().to_owned();
-                    parse_meta
-                        .metadata
-                        .insert(key, MetaValue::MetaString(value));
+                    parse_meta.metadata.insert(key, value);
                 }
 
                 if !parse_meta.tree.goto_next_sibling() {
@@ -113,81 +76,5 @@ pub(in crate::block) fn meta(parse_meta: &mut Meta) -> Block {
 
     parse_meta.tree.goto_parent();
 
-    Block::Null
-}
-
-pub(in crate::block) fn general(parse_meta: &mut Meta) -> Block {
-    let mut classes = vec![parse_meta
-        .tree
-        .node()
-        .utf8_text(parse_meta.source)
-        .unwrap()
-        .to_owned()];
-
-    loop {
-        if !parse_meta.tree.goto_next_sibling() || parse_meta.tree.node().kind() != "_space" {
-            break;
-        }
-    }
-
-    if parse_meta.tree.node().kind() == "tag_parameters" {
-        if !parse_meta.tree.goto_first_child() {
-            unreachable!()
-        }
-
-        loop {
-            if parse_meta.tree.node().kind() == "tag_param" {
-                classes.push(
-                    parse_meta
-                        .tree
-                        .node()
-                        .utf8_text(parse_meta.source)
-                        .unwrap()
-                        .to_owned(),
-                );
-            }
-            if !parse_meta.tree.goto_next_sibling() {
-                break;
-            }
-        }
-
-        parse_meta.tree.goto_parent();
-    }
-
-    while parse_meta.tree.node().kind() != "ranged_verbatim_tag_content" {
-        if !parse_meta.tree.goto_next_sibling() {
-            unreachable!()
-        }
-    }
-
-    let content = parse_meta.tree.node().utf8_text(parse_meta.source).unwrap();
-
-    if classes.first().unwrap() == "table" {
-        let mut pandoc_command = Command::new("pandoc")
-            .arg("--from=gfm")
-            .arg("--to=json")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .expect("Couldn't spawn pandoc");
-        let mut stdin = pandoc_command.stdin.take().unwrap();
-        stdin
-            .write_all(content.as_bytes())
-            .expect("Couldnt write to pandocs stdin");
-        stdin.flush().unwrap();
-        drop(stdin);
-        if let Ok(output) = pandoc_command.wait_with_output() {
-            let output = String::from_utf8_lossy(&output.stdout);
-            let table = pandoc_ast::Pandoc::from_json(&output).blocks;
-            if table.len() == 1 && matches!(table.first().unwrap(), Block::Table(_, _, _, _, _, _))
-            {
-                parse_meta.tree.goto_parent();
-                return Block::Div((String::new(), Vec::from(&classes[1..]), vec![]), table);
-            }
-        }
-    }
-
-    parse_meta.tree.goto_parent();
-
-    Block::CodeBlock((String::new(), classes, vec![]), content.to_string())
+    Ok(())
 }
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use tree_sitter::Parser;
+
+use crate::{
+    document, norg, pandoc, round_trip, writer, Meta, ParseError, ParseOptions, Parsed, ParsedNorg,
+    RoundTrip,
+};
+
+/// Converts norg files with the same options, reusing its tree sitter parser between files.
+///
+/// A converter can't be shared between threads, but it can be cloned for every thread. The
+/// results don't borrow from it and can be sent anywhere
+pub struct Converter {
+    options: ParseOptions,
+    /// Clones only get one when they parse their first file, so cloning can't fail
+    parser: Option<Parser>,
+}
+
+impl Converter {
+    /// Fails if the norg grammar can't be loaded
+    pub fn new(options: ParseOptions) -> Result<Self, ParseError> {
+        Ok(Converter {
+            options,
+            parser: Some(norg_parser()?),
+        })
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parses a norg file into a pandoc AST.
+    ///
+    /// This is [`Converter::parse_norg`] followed by [`Converter::lower`]
+    pub fn parse(&mut self, file: &str) -> Result<Parsed, ParseError> {
+        let parsed = self.parse_norg(file)?;
+        Ok(self.lower_parsed(parsed))
+    }
+
+    /// Like [`Converter::parse`], but the source positions name the file they come from
+    pub fn parse_named(&mut self, name: &str, file: &str) -> Result<Parsed, ParseError> {
+        let mut parsed = self.parse_norg(file)?;
+        parsed.document.name = Some(name.to_owned());
+        Ok(self.lower_parsed(parsed))
+    }
+
+    /// Lowers a parsed document and puts the diagnostics of both steps together
+    fn lower_parsed(&self, mut parsed: ParsedNorg) -> Parsed {
+        let mut lowered = self.lower(&parsed.document);
+        parsed.diagnostics.append(&mut lowered.diagnostics);
+        lowered.diagnostics = parsed.diagnostics;
+        lowered
+    }
+
+    /// Converts a norg file to pandoc, writes that back as norg and reports everything that's
+    /// different once it's converted again
+    pub fn round_trip(&mut self, file: &str) -> Result<RoundTrip, ParseError> {
+        let options = self.options.clone().source_positions(false);
+        let original = self.parse_norg(file)?;
+        let positioned = pandoc::lower(&original.document, &options.clone().source_positions(true));
+        let mut lowered = pandoc::lower(&original.document, &options);
+
+        let written = writer::write_norg(&lowered.ast);
+        let round_tripped = self.parse_norg(&written.source)?;
+        let round_tripped = pandoc::lower(&round_tripped.document, &options);
+
+        let mut diagnostics = original.diagnostics;
+        diagnostics.append(&mut lowered.diagnostics);
+        diagnostics.extend(written.diagnostics);
+
+        Ok(RoundTrip {
+            differences: round_trip::compare(file, &positioned.ast, &round_tripped.ast),
+            source: written.source,
+            diagnostics,
+        })
+    }
+
+    /// Parses a norg file into the typed norg representation
+    pub fn parse_norg(&mut self, file: &str) -> Result<ParsedNorg, ParseError> {
+        let parser = match &mut self.parser {
+            Some(parser) => parser,
+            None => self.parser.insert(norg_parser()?),
+        };
+        let tree = parser
+            .parse(file, None)
+            .ok_or_else(|| ParseError::without_node("tree sitter couldn't parse the file"))?;
+
+        #[cfg(feature = "debug")]
+        {
+            crate::debug_tree(
+                &mut Meta {
+                    tree: tree.walk(),
+                    source: file.as_bytes(),
+                    metadata: BTreeMap::new(),
+                    diagnostics: vec![],
+                },
+                0,
+            );
+        }
+
+        document::parse(Meta {
+            tree: tree.walk(),
+            source: file.as_bytes(),
+            metadata: BTreeMap::new(),
+            diagnostics: vec![],
+        })
+    }
+
+    /// Lowers a document in the typed norg representation into a pandoc AST
+    pub fn lower(&self, document: &norg::Document) -> Parsed {
+        pandoc::lower(document, &self.options)
+    }
+}
+
+impl Clone for Converter {
+    /// Creates a converter with the same options, it gets a parser of its own once it's used
+    fn clone(&self) -> Self {
+        Converter {
+            options: self.options.clone(),
+            parser: None,
+        }
+    }
+}
+
+/// A tree sitter parser for norg, fails if the norg grammar can't be loaded
+fn norg_parser() -> Result<Parser, ParseError> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(tree_sitter_norg::language())
+        .map_err(|e| ParseError::without_node(format!("couldn't load the norg grammar: {e}")))?;
+    Ok(parser)
+}
+
+// Makes sure the results and the converter itself can be moved to other threads
+const _: fn() = || {
+    fn is_send<T: Send>() {}
+    is_send::<Converter>();
+    is_send::<Parsed>();
+    is_send::<ParsedNorg>();
+    is_send::<RoundTrip>();
+    is_send::<ParseError>();
+};
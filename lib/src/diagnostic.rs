@@ -0,0 +1,117 @@
+use std::{fmt::Display, ops::Range};
+
+use tree_sitter::Node;
+
+use crate::ParseError;
+
+/// Where in the source a node is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub byte_range: Range<usize>,
+    /// The 1-based line the node starts at
+    pub line: usize,
+    /// The 1-based column the node starts at
+    pub column: usize,
+    /// The 1-based line the node ends at
+    pub end_line: usize,
+    /// The 1-based column right after the end of the node
+    pub end_column: usize,
+}
+
+impl Span {
+    pub(crate) fn new(node: Node) -> Self {
+        Span {
+            byte_range: node.byte_range(),
+            line: node.start_position().row + 1,
+            column: node.start_position().column + 1,
+            end_line: node.end_position().row + 1,
+            end_column: node.end_position().column + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Something that went wrong while converting a document, but didn't stop the conversion
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Where it happened, if it can be traced back to the source
+    pub span: Option<Span>,
+    /// The tree sitter kind of the node it happened at
+    pub node_kind: Option<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn warning(node: Node, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            span: Some(Span::new(node)),
+            node_kind: Some(node.kind().to_owned()),
+        }
+    }
+
+    /// Turns an error into a warning, for when the parser could recover from it
+    pub(crate) fn recovered(error: ParseError) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: format!("{}, keeping the raw text", error.message),
+            span: Some(Span {
+                byte_range: error.byte_range,
+                line: error.line,
+                column: error.column,
+                end_line: error.end_line,
+                end_column: error.end_column,
+            }),
+            node_kind: Some(error.node_kind),
+        }
+    }
+
+    /// A warning about an element of the typed norg representation, e.g. from link resolution
+    pub(crate) fn warning_at(span: &Span, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            span: Some(span.clone()),
+            node_kind: None,
+        }
+    }
+
+    /// A warning that has no place in the norg source, e.g. from writing norg
+    pub(crate) fn warning_without_span(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            span: None,
+            node_kind: None,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.line, span.column)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if let Some(node_kind) = &self.node_kind {
+            write!(f, " ({node_kind})")?;
+        }
+        Ok(())
+    }
+}
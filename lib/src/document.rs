@@ -1,19 +1,17 @@
-use pandoc_ast::Pandoc;
+use crate::{block, norg::Document, Meta, ParseError, ParsedNorg};
 
-use crate::{block, inline::link, Meta};
-
-pub(super) fn parse(mut parse_meta: Meta, api_version: Vec<u32>) -> Pandoc {
-    let mut blocks = if parse_meta.tree.goto_first_child() {
-        block::parse(&mut parse_meta).into()
-    } else {
-        vec![]
-    };
-
-    link::resolve_links(&mut blocks);
-
-    Pandoc {
-        meta: parse_meta.metadata,
-        blocks,
-        pandoc_api_version: api_version,
+pub(super) fn parse(mut parse_meta: Meta) -> Result<ParsedNorg, ParseError> {
+    let mut content = vec![];
+    if parse_meta.tree.goto_first_child() {
+        block::parse(&mut parse_meta, &mut content)?;
     }
+
+    Ok(ParsedNorg {
+        document: Document {
+            name: None,
+            metadata: parse_meta.metadata,
+            content,
+        },
+        diagnostics: parse_meta.diagnostics,
+    })
 }
@@ -0,0 +1,62 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+use tree_sitter::Node;
+
+/// An error that stops a document from being converted.
+///
+/// It points at the tree sitter node the parser couldn't handle
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    /// The tree sitter kind of the node
+    pub node_kind: String,
+    /// The byte range of the node in the source
+    pub byte_range: Range<usize>,
+    /// The 1-based line the node starts at
+    pub line: usize,
+    /// The 1-based column the node starts at
+    pub column: usize,
+    /// The 1-based line the node ends at
+    pub end_line: usize,
+    /// The 1-based column right after the end of the node
+    pub end_column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(node: Node, message: impl Into<String>) -> Self {
+        ParseError {
+            message: message.into(),
+            node_kind: node.kind().to_owned(),
+            byte_range: node.byte_range(),
+            line: node.start_position().row + 1,
+            column: node.start_position().column + 1,
+            end_line: node.end_position().row + 1,
+            end_column: node.end_position().column + 1,
+        }
+    }
+
+    /// An error that isn't tied to any node, e.g. when tree sitter itself fails
+    pub(crate) fn without_node(message: impl Into<String>) -> Self {
+        ParseError {
+            message: message.into(),
+            node_kind: String::new(),
+            byte_range: 0..0,
+            line: 1,
+            column: 1,
+            end_line: 1,
+            end_column: 1,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        if !self.node_kind.is_empty() {
+            write!(f, " ({})", self.node_kind)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
@@ -0,0 +1,309 @@
+use pandoc_ast::{Alignment, Block, Format, ListNumberStyle, Row};
+
+use super::{attributes, escape, Renderer};
+
+impl Renderer {
+    pub(super) fn blocks(&mut self, blocks: &[Block], output: &mut String) {
+        for block in blocks {
+            self.block(block, output);
+        }
+    }
+
+    fn block(&mut self, block: &Block, output: &mut String) {
+        match block {
+            Block::Plain(inlines) => {
+                self.inlines(inlines, output);
+                output.push('\n');
+            }
+
+            Block::Para(inlines) => {
+                output.push_str("<p>");
+                self.inlines(inlines, output);
+                output.push_str("</p>\n");
+            }
+
+            Block::LineBlock(lines) => {
+                output.push_str("<div class=\"line-block\">");
+                for (index, line) in lines.iter().enumerate() {
+                    if index > 0 {
+                        output.push_str("<br />\n");
+                    }
+                    self.inlines(line, output);
+                }
+                output.push_str("</div>\n");
+            }
+
+            Block::CodeBlock(attr, code) => output.push_str(&format!(
+                "<pre{}><code>{}</code></pre>\n",
+                attributes(attr),
+                escape(code)
+            )),
+
+            Block::RawBlock(Format(format), raw) => {
+                if format == "html" {
+                    output.push_str(raw);
+                    output.push('\n');
+                }
+            }
+
+            Block::BlockQuote(blocks) => {
+                output.push_str("<blockquote>\n");
+                self.blocks(blocks, output);
+                output.push_str("</blockquote>\n");
+            }
+
+            Block::OrderedList((start, style, _), items) => {
+                output.push_str("<ol");
+                if *start != 1 {
+                    output.push_str(&format!(" start=\"{start}\""));
+                }
+                let number_type = match style {
+                    ListNumberStyle::LowerAlpha => Some("a"),
+                    ListNumberStyle::UpperAlpha => Some("A"),
+                    ListNumberStyle::LowerRoman => Some("i"),
+                    ListNumberStyle::UpperRoman => Some("I"),
+                    ListNumberStyle::DefaultStyle
+                    | ListNumberStyle::Example
+                    | ListNumberStyle::Decimal => None,
+                };
+                if let Some(number_type) = number_type {
+                    output.push_str(&format!(" type=\"{number_type}\""));
+                }
+                output.push_str(">\n");
+                self.items(items, output);
+                output.push_str("</ol>\n");
+            }
+
+            Block::BulletList(items) => {
+                output.push_str("<ul>\n");
+                self.items(items, output);
+                output.push_str("</ul>\n");
+            }
+
+            Block::DefinitionList(items) => {
+                output.push_str("<dl>\n");
+                for (term, definitions) in items {
+                    output.push_str("<dt>");
+                    self.inlines(term, output);
+                    output.push_str("</dt>\n");
+                    for definition in definitions {
+                        output.push_str("<dd>\n");
+                        self.blocks(definition, output);
+                        output.push_str("</dd>\n");
+                    }
+                }
+                output.push_str("</dl>\n");
+            }
+
+            Block::Header(level, attr, inlines) => {
+                let level = (*level).clamp(1, 6);
+                output.push_str(&format!("<h{level}{}>", attributes(attr)));
+                self.inlines(inlines, output);
+                output.push_str(&format!("</h{level}>\n"));
+            }
+
+            Block::HorizontalRule => output.push_str("<hr />\n"),
+
+            Block::Table(attr, (_, caption), columns, (_, head), bodies, (_, foot)) => {
+                output.push_str(&format!("<table{}>\n", attributes(attr)));
+                if !caption.is_empty() {
+                    output.push_str("<caption>\n");
+                    self.blocks(caption, output);
+                    output.push_str("</caption>\n");
+                }
+
+                let alignments: Vec<&Alignment> =
+                    columns.iter().map(|(alignment, _)| alignment).collect();
+                if !head.is_empty() {
+                    output.push_str("<thead>\n");
+                    self.rows(head, "th", &alignments, output);
+                    output.push_str("</thead>\n");
+                }
+                for (_, _, intermediate_head, body) in bodies {
+                    output.push_str("<tbody>\n");
+                    self.rows(intermediate_head, "th", &alignments, output);
+                    self.rows(body, "td", &alignments, output);
+                    output.push_str("</tbody>\n");
+                }
+                if !foot.is_empty() {
+                    output.push_str("<tfoot>\n");
+                    self.rows(foot, "td", &alignments, output);
+                    output.push_str("</tfoot>\n");
+                }
+
+                output.push_str("</table>\n");
+            }
+
+            Block::Figure(attr, (_, caption), blocks) => {
+                output.push_str(&format!("<figure{}>\n", attributes(attr)));
+                self.blocks(blocks, output);
+                if !caption.is_empty() {
+                    output.push_str("<figcaption>");
+                    self.blocks(caption, output);
+                    output.push_str("</figcaption>\n");
+                }
+                output.push_str("</figure>\n");
+            }
+
+            Block::Div(attr, blocks) => {
+                output.push_str(&format!("<div{}>\n", attributes(attr)));
+                self.blocks(blocks, output);
+                output.push_str("</div>\n");
+            }
+
+            Block::Null => {}
+        }
+    }
+
+    fn items(&mut self, items: &[Vec<Block>], output: &mut String) {
+        for item in items {
+            output.push_str("<li>");
+            self.blocks(item, output);
+            output.push_str("</li>\n");
+        }
+    }
+
+    /// Renders table rows, the cells without an alignment of their own get the one of their
+    /// column
+    fn rows(&mut self, rows: &[Row], tag: &str, alignments: &[&Alignment], output: &mut String) {
+        for (attr, cells) in rows {
+            output.push_str(&format!("<tr{}>\n", attributes(attr)));
+            for (column, (attr, alignment, row_span, column_span, content)) in
+                cells.iter().enumerate()
+            {
+                output.push_str(&format!("<{tag}{}", attributes(attr)));
+                let alignment = match alignment {
+                    Alignment::AlignDefault => alignments.get(column).copied(),
+                    alignment => Some(alignment),
+                };
+                let alignment = match alignment {
+                    Some(Alignment::AlignLeft) => Some("left"),
+                    Some(Alignment::AlignRight) => Some("right"),
+                    Some(Alignment::AlignCenter) => Some("center"),
+                    Some(Alignment::AlignDefault) | None => None,
+                };
+                if let Some(alignment) = alignment {
+                    output.push_str(&format!(" style=\"text-align: {alignment};\""));
+                }
+                if *row_span > 1 {
+                    output.push_str(&format!(" rowspan=\"{row_span}\""));
+                }
+                if *column_span > 1 {
+                    output.push_str(&format!(" colspan=\"{column_span}\""));
+                }
+                output.push('>');
+                // Pandoc keeps single line cells free of paragraph tags
+                match content.as_slice() {
+                    [Block::Plain(inlines)] | [Block::Para(inlines)] => {
+                        self.inlines(inlines, output)
+                    }
+                    content => self.blocks(content, output),
+                }
+                output.push_str(&format!("</{tag}>\n"));
+            }
+            output.push_str("</tr>\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pandoc_ast::{Cell, ColWidth, Inline, ListNumberDelim};
+
+    use super::*;
+
+    fn render(blocks: &[Block]) -> String {
+        let mut output = String::new();
+        Renderer { notes: vec![] }.blocks(blocks, &mut output);
+        output
+    }
+
+    fn text(text: &str) -> Vec<Inline> {
+        vec![Inline::Str(text.to_owned())]
+    }
+
+    #[test]
+    fn headings_keep_their_id_and_clamp_the_level() {
+        let attr = (String::from("intro"), vec![], vec![]);
+        assert_eq!(
+            render(&[Block::Header(2, attr.clone(), text("Intro"))]),
+            "<h2 id=\"intro\">Intro</h2>\n"
+        );
+        assert_eq!(
+            render(&[Block::Header(9, attr, text("Deep"))]),
+            "<h6 id=\"intro\">Deep</h6>\n"
+        );
+    }
+
+    #[test]
+    fn ordered_lists_keep_their_start_and_style() {
+        let list = Block::OrderedList(
+            (3, ListNumberStyle::LowerRoman, ListNumberDelim::Period),
+            vec![vec![Block::Plain(text("a"))]],
+        );
+        assert_eq!(
+            render(&[list]),
+            "<ol start=\"3\" type=\"i\">\n<li>a\n</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn nested_lists_go_inside_the_item() {
+        let list = Block::BulletList(vec![vec![
+            Block::Plain(text("a")),
+            Block::BulletList(vec![vec![Block::Plain(text("b"))]]),
+        ]]);
+        assert_eq!(
+            render(&[list]),
+            "<ul>\n<li>a\n<ul>\n<li>b\n</li>\n</ul>\n</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn code_is_escaped() {
+        let code = Block::CodeBlock(
+            (String::new(), vec![String::from("rust")], vec![]),
+            String::from("a < b && c"),
+        );
+        assert_eq!(
+            render(&[code]),
+            "<pre class=\"rust\"><code>a &lt; b &amp;&amp; c</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn table_cells_get_the_alignment_of_their_column() {
+        let no_attr = || (String::new(), vec![], vec![]);
+        let cell = |content: &str| -> Cell {
+            (
+                no_attr(),
+                Alignment::AlignDefault,
+                1,
+                1,
+                vec![Block::Plain(text(content))],
+            )
+        };
+        let table = Block::Table(
+            no_attr(),
+            (None, vec![]),
+            vec![
+                (Alignment::AlignDefault, ColWidth::ColWidthDefault),
+                (Alignment::AlignRight, ColWidth::ColWidthDefault),
+            ],
+            (no_attr(), vec![(no_attr(), vec![cell("a"), cell("b")])]),
+            vec![(
+                no_attr(),
+                0,
+                vec![],
+                vec![(no_attr(), vec![cell("1"), cell("<2>")])],
+            )],
+            (no_attr(), vec![]),
+        );
+        assert_eq!(
+            render(&[table]),
+            "<table>\n<thead>\n<tr>\n<th>a</th>\n<th style=\"text-align: right;\">b</th>\n\
+             </tr>\n</thead>\n<tbody>\n<tr>\n<td>1</td>\n\
+             <td style=\"text-align: right;\">&lt;2&gt;</td>\n</tr>\n</tbody>\n</table>\n"
+        );
+    }
+}
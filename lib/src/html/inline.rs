@@ -0,0 +1,126 @@
+use pandoc_ast::{Format, Inline, MathType, QuoteType};
+
+use crate::writer::inline::plain_text;
+
+use super::{attributes, escape, Renderer};
+
+impl Renderer {
+    pub(super) fn inlines(&mut self, inlines: &[Inline], output: &mut String) {
+        for inline in inlines {
+            self.inline(inline, output);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline, output: &mut String) {
+        match inline {
+            Inline::Str(text) => output.push_str(&escape(text)),
+
+            Inline::Emph(content) => self.tagged("em", content, output),
+            Inline::Underline(content) => self.tagged("u", content, output),
+            Inline::Strong(content) => self.tagged("strong", content, output),
+            Inline::Strikeout(content) => self.tagged("del", content, output),
+            Inline::Superscript(content) => self.tagged("sup", content, output),
+            Inline::Subscript(content) => self.tagged("sub", content, output),
+
+            Inline::SmallCaps(content) => {
+                output.push_str("<span class=\"smallcaps\">");
+                self.inlines(content, output);
+                output.push_str("</span>");
+            }
+
+            Inline::Quoted(quote_type, content) => {
+                let (open, close) = match quote_type {
+                    QuoteType::SingleQuote => ('‘', '’'),
+                    QuoteType::DoubleQuote => ('“', '”'),
+                };
+                output.push(open);
+                self.inlines(content, output);
+                output.push(close);
+            }
+
+            Inline::Cite(_, content) => {
+                output.push_str("<span class=\"citation\">");
+                self.inlines(content, output);
+                output.push_str("</span>");
+            }
+
+            Inline::Code(attr, code) => output.push_str(&format!(
+                "<code{}>{}</code>",
+                attributes(attr),
+                escape(code)
+            )),
+
+            Inline::Space => output.push(' '),
+
+            Inline::SoftBreak => output.push('\n'),
+
+            Inline::LineBreak => output.push_str("<br />\n"),
+
+            Inline::Math(MathType::InlineMath, math) => output.push_str(&format!(
+                "<span class=\"math inline\">\\({}\\)</span>",
+                escape(math)
+            )),
+
+            Inline::Math(MathType::DisplayMath, math) => output.push_str(&format!(
+                "<span class=\"math display\">\\[{}\\]</span>",
+                escape(math)
+            )),
+
+            Inline::RawInline(Format(format), raw) => {
+                if format == "html" {
+                    output.push_str(raw);
+                }
+            }
+
+            Inline::Link(attr, content, (url, title)) => {
+                output.push_str(&format!("<a href=\"{}\"", escape(url)));
+                if !title.is_empty() {
+                    output.push_str(&format!(" title=\"{}\"", escape(title)));
+                }
+                output.push_str(&attributes(attr));
+                output.push('>');
+                self.inlines(content, output);
+                output.push_str("</a>");
+            }
+
+            Inline::Image(attr, description, (source, title)) => {
+                output.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\"",
+                    escape(source),
+                    escape(&plain_text(description))
+                ));
+                if !title.is_empty() {
+                    output.push_str(&format!(" title=\"{}\"", escape(title)));
+                }
+                output.push_str(&attributes(attr));
+                output.push_str(" />");
+            }
+
+            Inline::Note(content) => {
+                // Reserve the number first, notes inside the note come after it
+                self.notes.push(String::new());
+                let number = self.notes.len();
+                let mut note = String::new();
+                self.blocks(content, &mut note);
+                self.notes[number - 1] = note;
+
+                output.push_str(&format!(
+                    "<a href=\"#fn{number}\" class=\"footnote-ref\" id=\"fnref{number}\" \
+                     role=\"doc-noteref\"><sup>{number}</sup></a>"
+                ));
+            }
+
+            Inline::Span(attr, content) => {
+                output.push_str(&format!("<span{}>", attributes(attr)));
+                self.inlines(content, output);
+                output.push_str("</span>");
+            }
+        }
+    }
+
+    fn tagged(&mut self, tag: &str, content: &[Inline], output: &mut String) {
+        output.push_str(&format!("<{tag}>"));
+        self.inlines(content, output);
+        output.push_str(&format!("</{tag}>"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Renders a pandoc AST as html without pandoc, for previews that have to be fast
+
+use pandoc_ast::{Attr, MetaValue, Pandoc};
+
+use crate::writer;
+
+mod block;
+mod inline;
+
+struct Renderer {
+    /// The rendered content of the footnotes, they are put at the end of the document
+    notes: Vec<String>,
+}
+
+/// Renders a pandoc AST as a standalone html document.
+///
+/// The output is close to what pandoc's html writer produces, but it has no templates, syntax
+/// highlighting or math rendering
+pub fn render_html(pandoc: &Pandoc) -> String {
+    let mut renderer = Renderer { notes: vec![] };
+
+    let mut body = String::new();
+    renderer.blocks(&pandoc.blocks, &mut body);
+    renderer.notes(&mut body);
+
+    let title = match pandoc.meta.get("title") {
+        Some(MetaValue::MetaInlines(inlines)) => writer::inline::plain_text(inlines),
+        Some(MetaValue::MetaString(title)) => title.clone(),
+        Some(MetaValue::MetaBlocks(blocks)) => writer::block::plain_text(blocks),
+        _ => String::new(),
+    };
+
+    let mut output = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\" />\n");
+    output.push_str(&format!("<title>{}</title>\n", escape(&title)));
+    output.push_str("</head>\n<body>\n");
+    if !title.is_empty() {
+        output.push_str(&format!("<h1 class=\"title\">{}</h1>\n", escape(&title)));
+    }
+    output.push_str(&body);
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+impl Renderer {
+    /// Renders the footnotes collected so far as the list pandoc puts at the end
+    fn notes(&mut self, output: &mut String) {
+        if self.notes.is_empty() {
+            return;
+        }
+
+        output.push_str("<section class=\"footnotes\" role=\"doc-endnotes\">\n<hr />\n<ol>\n");
+        for (index, note) in self.notes.iter().enumerate() {
+            let number = index + 1;
+            output.push_str(&format!(
+                "<li id=\"fn{number}\">{note}<a href=\"#fnref{number}\" class=\"footnote-back\" \
+                 role=\"doc-backlink\">↩︎</a></li>\n"
+            ));
+        }
+        output.push_str("</ol>\n</section>\n");
+    }
+}
+
+/// Renders the attributes of an element, including the leading space.
+///
+/// Keys html doesn't know get a `data-` prefix, the same way pandoc does it
+fn attributes((id, classes, attributes): &Attr) -> String {
+    let mut output = String::new();
+    if !id.is_empty() {
+        output.push_str(&format!(" id=\"{}\"", escape(id)));
+    }
+    if !classes.is_empty() {
+        output.push_str(&format!(" class=\"{}\"", escape(&classes.join(" "))));
+    }
+    for (key, value) in attributes {
+        let key = match key.as_str() {
+            "style" | "title" | "lang" | "dir" | "width" | "height" => key.clone(),
+            key if key.starts_with("data-") => key.to_owned(),
+            key => format!("data-{key}"),
+        };
+        output.push_str(&format!(" {key}=\"{}\"", escape(value)));
+    }
+    output
+}
+
+/// Escapes text for html content and attribute values
+fn escape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            c => output.push(c),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use pandoc_ast::{Block, Inline, Map};
+
+    use super::*;
+
+    #[test]
+    fn escapes_markup_characters() {
+        assert_eq!(
+            escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(escape("plain 'text'"), "plain 'text'");
+    }
+
+    #[test]
+    fn unknown_attributes_get_a_data_prefix() {
+        let attr = (
+            String::from("intro"),
+            vec![String::from("a"), String::from("b")],
+            vec![
+                (String::from("lang"), String::from("en")),
+                (String::from("pos"), String::from("1:1-1:5")),
+                (String::from("data-x"), String::from("\"")),
+            ],
+        );
+        assert_eq!(
+            attributes(&attr),
+            " id=\"intro\" class=\"a b\" lang=\"en\" data-pos=\"1:1-1:5\" data-x=\"&quot;\""
+        );
+    }
+
+    #[test]
+    fn renders_the_title_and_the_footnotes() {
+        let mut meta = Map::new();
+        meta.insert(
+            String::from("title"),
+            MetaValue::MetaString(String::from("Notes & more")),
+        );
+        let pandoc = Pandoc {
+            meta,
+            blocks: vec![Block::Para(vec![
+                Inline::Str(String::from("text")),
+                Inline::Note(vec![Block::Para(vec![Inline::Str(String::from("note"))])]),
+            ])],
+            pandoc_api_version: vec![1, 23],
+        };
+
+        let html = render_html(&pandoc);
+        assert!(html.contains("<title>Notes &amp; more</title>"));
+        assert!(html.contains("<h1 class=\"title\">Notes &amp; more</h1>"));
+        assert!(html.contains(
+            "<p>text<a href=\"#fn1\" class=\"footnote-ref\" id=\"fnref1\" \
+             role=\"doc-noteref\"><sup>1</sup></a></p>"
+        ));
+        assert!(html.contains("<li id=\"fn1\"><p>note</p>\n<a href=\"#fnref1\""));
+    }
+}
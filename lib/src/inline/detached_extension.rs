@@ -1,30 +1,30 @@
-use std::collections::LinkedList;
+use crate::{
+    norg::{DetachedExtension, TodoStatus},
+    Meta, ParseError,
+};
 
-use pandoc_ast::Inline;
+pub(crate) fn parse(parse_meta: &mut Meta) -> Result<DetachedExtension, ParseError> {
+    parse_meta.first_child()?;
+    parse_meta.next_sibling()?;
 
-use crate::Meta;
-
-pub(crate) fn parse(parse_meta: &mut Meta) -> LinkedList<Inline> {
-    if !parse_meta.tree.goto_first_child() || !parse_meta.tree.goto_next_sibling() {
-        unreachable!()
-    }
-    let item = if parse_meta.tree.node().kind() == "todo_item_urgent" {
-        LinkedList::from([
-            Inline::Strong(vec![Inline::Span(
-                (
-                    String::default(),
-                    vec!["todo".to_string(), "urgent".to_string()],
-                    vec![],
-                ),
-                vec![Inline::Str("(!)".to_string())],
-            )]),
-            Inline::Space,
-        ])
-    } else {
-        eprintln!("{}", parse_meta.tree.node().kind());
-        todo!()
+    let status = match parse_meta.tree.node().kind() {
+        "todo_item_undone" => TodoStatus::Undone,
+        "todo_item_done" => TodoStatus::Done,
+        "todo_item_uncertain" => TodoStatus::NeedsInput,
+        "todo_item_urgent" => TodoStatus::Urgent,
+        "todo_item_recurring" => TodoStatus::Recurring,
+        "todo_item_pending" => TodoStatus::Pending,
+        "todo_item_on_hold" => TodoStatus::OnHold,
+        "todo_item_cancelled" => TodoStatus::Cancelled,
+        _ => {
+            parse_meta.warn("not supported yet, keeping the raw text");
+            parse_meta.tree.goto_parent();
+            return Ok(DetachedExtension::Unsupported(
+                parse_meta.text()?.to_owned(),
+            ));
+        }
     };
 
     parse_meta.tree.goto_parent();
-    item
+    Ok(DetachedExtension::Todo(status))
 }
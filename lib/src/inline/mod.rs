@@ -1,133 +1,111 @@
-use std::collections::LinkedList;
+use crate::{
+    norg::{AttachedModifier, Inline, InlineKind},
+    Meta, ParseError,
+};
 
-use pandoc_ast::Inline;
+mod attached;
+pub(crate) mod detached_extension;
+mod link;
+
+/// Parses the inline under the cursor and all of its following siblings into `inlines`.
+///
+/// Afterwards the cursor is moved back up to the parent
+pub(super) fn parse(parse_meta: &mut Meta, inlines: &mut Vec<Inline>) -> Result<(), ParseError> {
+    loop {
+        parse_inline(parse_meta, inlines)?;
+
+        if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            return Ok(());
+        }
+    }
+}
 
-use crate::Meta;
+/// Like [`parse`], but collects the inlines into a new `Vec`
+pub(super) fn collect(parse_meta: &mut Meta) -> Result<Vec<Inline>, ParseError> {
+    let mut inlines = vec![];
+    parse(parse_meta, &mut inlines)?;
+    Ok(inlines)
+}
 
-mod attached;
-pub mod detached_extension;
-pub mod link;
+/// Parses the inline under the cursor into `inlines` without moving on to its siblings
+fn parse_inline(parse_meta: &mut Meta, inlines: &mut Vec<Inline>) -> Result<(), ParseError> {
+    let span = parse_meta.span();
+
+    let kind = match parse_meta.tree.node().kind() {
+        _ if parse_meta.tree.node().is_error() => {
+            parse_meta.warn("tree sitter couldn't parse this, keeping the raw text");
+            InlineKind::Error(parse_meta.text()?.to_owned())
+        }
+
+        kind if parse_meta.tree.node().is_missing() => {
+            parse_meta.warn(format!("missing {kind}"));
+            return Ok(());
+        }
 
-pub(super) fn parse(parse_meta: &mut Meta) -> LinkedList<Inline> {
-    let mut inlines = match parse_meta.tree.node().kind() {
         "paragraph" | "paragraph_segment" => {
             if parse_meta.tree.goto_first_child() {
-                parse(parse_meta)
-            } else {
-                LinkedList::new()
+                parse(parse_meta, inlines)?;
             }
+            return Ok(());
         }
 
-        "bold" => LinkedList::from([attached::parse(parse_meta, attached::Type::Bold)]),
+        "bold" => attached::parse(parse_meta, AttachedModifier::Bold)?,
 
-        "italic" => LinkedList::from([attached::parse(parse_meta, attached::Type::Italic)]),
+        "italic" => attached::parse(parse_meta, AttachedModifier::Italic)?,
 
-        "underline" => LinkedList::from([attached::parse(parse_meta, attached::Type::Underline)]),
+        "underline" => attached::parse(parse_meta, AttachedModifier::Underline)?,
 
-        "strikethrough" => {
-            LinkedList::from([attached::parse(parse_meta, attached::Type::Strikethrough)])
-        }
+        "strikethrough" => attached::parse(parse_meta, AttachedModifier::Strikethrough)?,
 
-        "spoiler" => LinkedList::from([attached::parse(parse_meta, attached::Type::Spoiler)]),
+        "spoiler" => attached::parse(parse_meta, AttachedModifier::Spoiler)?,
 
-        "superscript" => {
-            LinkedList::from([attached::parse(parse_meta, attached::Type::Superscript)])
-        }
+        "superscript" => attached::parse(parse_meta, AttachedModifier::Superscript)?,
+
+        "subscript" => attached::parse(parse_meta, AttachedModifier::Subscript)?,
 
-        "subscript" => LinkedList::from([attached::parse(parse_meta, attached::Type::Subscript)]),
+        "inline_comment" => attached::parse(parse_meta, AttachedModifier::InlineComment)?,
 
-        "verbatim" => LinkedList::from([attached::parse(parse_meta, attached::Type::Code)]),
+        "verbatim" => attached::parse_verbatim(parse_meta)?,
 
-        "link" => LinkedList::from([link::parse(parse_meta)]),
+        "link" => link::parse(parse_meta)?,
 
-        "anchor_declaration" => LinkedList::from([link::parse_anchor_declaration(parse_meta)]),
+        "anchor_declaration" => link::parse_anchor_declaration(parse_meta)?,
 
-        "anchor_definition" => LinkedList::from([link::parse_anchor_definition(parse_meta)]),
+        "anchor_definition" => link::parse_anchor_definition(parse_meta)?,
 
         "escape_sequence" => {
-            if parse_meta.tree.goto_first_child() && parse_meta.tree.goto_next_sibling() {
-                let char = parse_meta
-                    .tree
-                    .node()
-                    .utf8_text(parse_meta.source)
-                    .unwrap()
-                    .to_owned();
-                parse_meta.tree.goto_parent();
-                LinkedList::from([Inline::Str(char)])
-            } else {
-                unreachable!()
-            }
+            parse_meta.first_child()?;
+            parse_meta.next_sibling()?;
+            let char = parse_meta.text()?.to_owned();
+            parse_meta.tree.goto_parent();
+            InlineKind::Text(char)
         }
 
         "inline_link_target" => {
             let content = if parse_meta.tree.goto_first_child() {
-                parse(parse_meta).into_iter().collect()
+                collect(parse_meta)?
             } else {
                 vec![]
             };
 
-            let id = format!("{} inl", to_string(&content));
-
-            LinkedList::from([Inline::Span((id, vec![], vec![]), content)])
+            InlineKind::InlineLinkTarget(content)
         }
 
-        "_line_break" => LinkedList::from([Inline::SoftBreak]),
+        "_line_break" => InlineKind::SoftBreak,
 
-        "_word" => LinkedList::from([Inline::Str(
-            parse_meta
-                .tree
-                .node()
-                .utf8_text(parse_meta.source)
-                .unwrap()
-                .to_owned(),
-        )]),
+        "_word" => InlineKind::Text(parse_meta.text()?.to_owned()),
 
-        "_begin" | "_end" | "_close" | "_open" => LinkedList::new(),
+        "_begin" | "_end" | "_close" | "_open" => return Ok(()),
 
-        "_space" => LinkedList::from([Inline::Space]),
+        "_space" => InlineKind::Space,
 
         _ => {
-            eprintln!("{} not implemented", parse_meta.tree.node().kind());
-            LinkedList::from([Inline::Str(
-                parse_meta
-                    .tree
-                    .node()
-                    .utf8_text(parse_meta.source)
-                    .unwrap()
-                    .to_owned(),
-            )])
+            parse_meta.warn("not supported yet, keeping the raw text");
+            InlineKind::Text(parse_meta.text()?.to_owned())
         }
     };
 
-    if parse_meta.tree.goto_next_sibling() {
-        let mut following_inlines = parse(parse_meta);
-        inlines.append(&mut following_inlines);
-    } else {
-        parse_meta.tree.goto_parent();
-    };
-
-    inlines
-}
-
-pub fn to_string(inlines: &[Inline]) -> String {
-    let mut output = String::new();
-
-    for inline in inlines {
-        match inline {
-            Inline::Code(_, s) | Inline::Str(s) => output.push_str(s),
-            Inline::Span(_, m)
-            | Inline::Link(_, m, _)
-            | Inline::Emph(m)
-            | Inline::Underline(m)
-            | Inline::Strong(m)
-            | Inline::Strikeout(m)
-            | Inline::Superscript(m)
-            | Inline::Subscript(m) => output.push_str(&to_string(m)),
-            Inline::Space => output.push(' '),
-            Inline::SoftBreak => output.push('\n'),
-            _ => unreachable!(),
-        }
-    }
-
-    output
+    inlines.push(Inline { kind, span });
+    Ok(())
 }
@@ -1,74 +1,154 @@
-use std::path::Path;
+use std::collections::BTreeMap;
 
-use pandoc_ast::{Map, MetaValue, Pandoc};
-use tree_sitter::{Parser, TreeCursor};
+use pandoc_ast::Pandoc;
+use tree_sitter::TreeCursor;
 
 mod block;
+mod converter;
+mod diagnostic;
 mod document;
+mod error;
+mod html;
 mod inline;
+mod links;
+pub mod norg;
+mod options;
+mod pandoc;
+mod round_trip;
+pub mod visit;
+mod writer;
+
+pub use converter::Converter;
+pub use diagnostic::{Diagnostic, Severity, Span};
+pub use error::ParseError;
+pub use html::render_html;
+pub use links::{AmbiguousLink, BrokenLink, DuplicateTarget, FileLink, LinkReport, TargetKind};
+pub use options::{
+    Comments, DeepHeadings, HeadingIds, LinkExtension, ParseOptions, DEFAULT_API_VERSION,
+};
+pub use round_trip::{Change, Difference, RoundTrip};
+pub use writer::{write_norg, Written};
+
+/// A converted document together with everything that went wrong along the way
+pub struct Parsed {
+    pub ast: Pandoc,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The links that couldn't be resolved unambiguously. Ambiguous links are also in the
+    /// diagnostics, broken ones only here
+    pub links: LinkReport,
+}
+
+/// A document in the typed norg representation together with everything that went wrong while
+/// parsing it
+pub struct ParsedNorg {
+    pub document: norg::Document,
+    pub diagnostics: Vec<Diagnostic>,
+}
 
 struct Meta<'a> {
     tree: TreeCursor<'a>,
     source: &'a [u8],
-    metadata: Map<String, MetaValue>,
-    target_format: &'a str,
-    workspace_root: &'a Path,
+    metadata: BTreeMap<String, String>,
+    diagnostics: Vec<Diagnostic>,
 }
 
-pub fn parse(
-    file: &str,
-    target_format: &str,
-    api_version: Vec<u32>,
-    workspace_root: &Path,
-) -> Pandoc {
-    let language = tree_sitter_norg::language();
-    let mut parser = Parser::new();
-    parser.set_language(language).unwrap();
-
-    let unparsed = file;
-    let tree = parser.parse(&unparsed, None).unwrap();
-
-    #[cfg(feature = "debug")]
-    {
-        debug_tree(
-            &mut Meta {
-                tree: tree.walk(),
-                source: unparsed.as_bytes(),
-                metadata: Map::default(),
-                target_format,
-                workspace_root,
-            },
-            0,
-        );
+impl<'a> Meta<'a> {
+    /// Creates an error pointing at the node under the cursor
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.tree.node(), message)
+    }
+
+    /// Records a warning about the node under the cursor
+    fn warn(&mut self, message: impl Into<String>) {
+        let diagnostic = Diagnostic::warning(self.tree.node(), message);
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Moves the cursor to the first child, failing if there is none
+    fn first_child(&mut self) -> Result<(), ParseError> {
+        if self.tree.goto_first_child() {
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "expected {} to have content",
+                self.tree.node().kind()
+            )))
+        }
+    }
+
+    /// Moves the cursor to the next sibling, failing if there is none
+    fn next_sibling(&mut self) -> Result<(), ParseError> {
+        if self.tree.goto_next_sibling() {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected more after {}", self.tree.node().kind())))
+        }
+    }
+
+    /// Where the node under the cursor is in the source
+    fn span(&self) -> Span {
+        Span::new(self.tree.node())
     }
 
-    document::parse(
-        Meta {
-            tree: tree.walk(),
-            source: unparsed.as_bytes(),
-            metadata: Map::default(),
-            target_format,
-            workspace_root,
-        },
-        api_version,
-    )
+    /// The source text of the node under the cursor
+    fn text(&self) -> Result<&'a str, ParseError> {
+        self.tree
+            .node()
+            .utf8_text(self.source)
+            .map_err(|_| self.error("source is not valid UTF-8"))
+    }
+
+    /// The nesting level tree sitter encodes in node kinds like `heading3`
+    fn kind_nesting(&self) -> Result<usize, ParseError> {
+        let kind = self.tree.node().kind();
+        kind.find(|c: char| c.is_ascii_digit())
+            .and_then(|number_index| kind[number_index..].parse().ok())
+            .ok_or_else(|| self.error(format!("{kind} has no nesting level")))
+    }
+}
+
+/// Parses a norg file into a pandoc AST.
+///
+/// This is [`parse_norg`] followed by [`lower`]. Fails if the tree sitter tree has a shape the
+/// parser can't handle. Anything that could still be converted, but maybe not the way it was
+/// meant, is reported in the diagnostics. Use a [`Converter`] to convert several files
+pub fn parse(file: &str, options: &ParseOptions) -> Result<Parsed, ParseError> {
+    Converter::new(options.clone())?.parse(file)
+}
+
+/// Parses a norg file into the typed norg representation
+pub fn parse_norg(file: &str) -> Result<ParsedNorg, ParseError> {
+    Converter::new(ParseOptions::default())?.parse_norg(file)
+}
+
+/// Lowers a document in the typed norg representation into a pandoc AST.
+///
+/// Links are resolved here, anything that can't be resolved is reported in the diagnostics
+pub fn lower(document: &norg::Document, options: &ParseOptions) -> Parsed {
+    pandoc::lower(document, options)
+}
+
+/// Converts a norg file to pandoc and back, see [`Converter::round_trip`]
+pub fn round_trip(file: &str, options: &ParseOptions) -> Result<RoundTrip, ParseError> {
+    Converter::new(options.clone())?.round_trip(file)
 }
 
 #[cfg(feature = "debug")]
 fn debug_tree(parse_meta: &mut Meta, indentlevel: usize) {
     let indent = " ".repeat(indentlevel * 3);
-    println!(
-        "{indent}{}",
-        //"{indent}{}: {}",
-        parse_meta.tree.node().kind(),
-        //parse_meta.tree.node().utf8_text(parse_meta.source).unwrap()
-    );
-    if parse_meta.tree.goto_first_child() {
-        debug_tree(parse_meta, indentlevel + 1);
-    }
-    if parse_meta.tree.goto_next_sibling() {
-        debug_tree(parse_meta, indentlevel);
-    } else {
-        parse_meta.tree.goto_parent();
+    loop {
+        println!(
+            "{indent}{}",
+            //"{indent}{}: {}",
+            parse_meta.tree.node().kind(),
+            //parse_meta.tree.node().utf8_text(parse_meta.source).unwrap()
+        );
+        if parse_meta.tree.goto_first_child() {
+            debug_tree(parse_meta, indentlevel + 1);
+        }
+        if !parse_meta.tree.goto_next_sibling() {
+            parse_meta.tree.goto_parent();
+            break;
+        }
     }
 }
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use crate::Span;
+
+/// What magic char, wiki, heading and anchor links resolved to and which other files are linked,
+/// collected while lowering
+#[derive(Debug, Clone, Default)]
+pub struct LinkReport {
+    /// Links whose target isn't defined in the document
+    pub broken: Vec<BrokenLink>,
+    /// Links that match more than one target, they point to the first one
+    pub ambiguous: Vec<AmbiguousLink>,
+    /// Targets with a name that was already used by a target of the same kind
+    pub duplicates: Vec<DuplicateTarget>,
+    /// Links to other norg files, in the order they appear
+    pub files: Vec<FileLink>,
+}
+
+impl LinkReport {
+    /// Whether every link inside the document resolved to exactly one target, links to other
+    /// files aren't checked
+    pub fn is_clean(&self) -> bool {
+        self.broken.is_empty() && self.ambiguous.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// The kinds of things links can point to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetKind {
+    Heading,
+    /// An inline link target like `<target>`
+    InlineLinkTarget,
+    /// An anchor definition like `[name]{target}`
+    Anchor,
+}
+
+impl Display for TargetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetKind::Heading => write!(f, "heading"),
+            TargetKind::InlineLinkTarget => write!(f, "inline link target"),
+            TargetKind::Anchor => write!(f, "anchor"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BrokenLink {
+    /// The target as written in the link
+    pub target: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct AmbiguousLink {
+    /// The target as written in the link
+    pub target: String,
+    pub span: Span,
+    /// Where the matching targets are defined, the link points to the first one
+    pub candidates: Vec<Span>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateTarget {
+    pub kind: TargetKind,
+    pub name: String,
+    pub span: Span,
+    /// Where the target with the same name was defined first
+    pub first: Span,
+}
+
+/// A link to another norg file like `{:$/notes/file:}`
+#[derive(Debug, Clone)]
+pub struct FileLink {
+    /// The path as written in the link, it's relative to the document unless it starts with `$`
+    pub path: String,
+    /// The path with the workspace resolved, without the extension
+    pub resolved: String,
+    pub span: Span,
+}
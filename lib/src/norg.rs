@@ -0,0 +1,338 @@
+//! A typed representation of a norg document.
+//!
+//! It sits between the tree sitter tree and the pandoc AST and keeps the norg semantics that
+//! pandoc has no place for, like todo states, carryover tags or the kind of a link target.
+//! Every node knows where in the source it came from
+
+use std::collections::BTreeMap;
+
+use crate::Span;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+    /// The name of the file the document was parsed from, it's put in front of the source
+    /// positions
+    pub name: Option<String>,
+    /// The key value pairs of the `@document.meta` tag
+    pub metadata: BTreeMap<String, String>,
+    pub content: Vec<Block>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockKind {
+    Paragraph(Vec<Inline>),
+    Heading(Heading),
+    /// The items of a list in document order, ordered and unordered items can be mixed
+    List(Vec<Nestable>),
+    /// The items of a quote in document order
+    Quote(Vec<Nestable>),
+    /// A ranged verbatim tag like `@code`, the `@document.meta` tag ends up in
+    /// [`Document::metadata`] instead
+    RangedTag(Tag),
+    /// An infirm tag like `.image`
+    InfirmTag(Tag),
+    /// A block that the carryover tags in front of it apply to
+    Carryover(Vec<Tag>, Box<Block>),
+    HorizontalRule,
+    /// Something that isn't supported yet, kept as raw text
+    Unsupported(String),
+    /// Something tree sitter couldn't parse, kept as raw text
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// The number of `*` in the prefix
+    pub level: usize,
+    pub extension: Option<DetachedExtension>,
+    pub title: Vec<Inline>,
+    /// From the prefix to the end of the title, without the content
+    pub line_span: Span,
+    /// Everything up to the next heading of the same or a lower level
+    pub content: Vec<Block>,
+}
+
+/// A single list or quote item.
+///
+/// Norg nests them by the length of the prefix, so they are kept flat
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nestable {
+    pub kind: NestableKind,
+    /// The number of repeated characters in the prefix
+    pub level: usize,
+    pub extension: Option<DetachedExtension>,
+    pub content: Vec<Block>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NestableKind {
+    Unordered,
+    Ordered,
+    Quote,
+}
+
+/// A detached modifier extension like the todo state in `- (!) item`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetachedExtension {
+    Todo(TodoStatus),
+    /// An extension that isn't supported yet, kept as raw text
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoStatus {
+    Undone,
+    Done,
+    NeedsInput,
+    Urgent,
+    Recurring,
+    Pending,
+    OnHold,
+    Cancelled,
+}
+
+impl TodoStatus {
+    /// The marker that's written in the source, e.g. `(!)` for urgent
+    pub fn marker(self) -> &'static str {
+        match self {
+            TodoStatus::Undone => "( )",
+            TodoStatus::Done => "(x)",
+            TodoStatus::NeedsInput => "(?)",
+            TodoStatus::Urgent => "(!)",
+            TodoStatus::Recurring => "(+)",
+            TodoStatus::Pending => "(-)",
+            TodoStatus::OnHold => "(=)",
+            TodoStatus::Cancelled => "(_)",
+        }
+    }
+}
+
+/// A ranged, infirm or carryover tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub parameters: Vec<String>,
+    /// The verbatim content of a ranged tag, empty for all other tags
+    pub content: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inline {
+    pub kind: InlineKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InlineKind {
+    Text(String),
+    Space,
+    SoftBreak,
+    Attached(AttachedModifier, Vec<Inline>),
+    /// The content of an inline verbatim `` `code` ``
+    Verbatim(String),
+    Link(Link),
+    /// An anchor without a target like `[name]`, it links to the definition with the same name
+    AnchorDeclaration(Vec<Inline>),
+    /// An anchor with a target like `[name]{target}`
+    AnchorDefinition(Vec<Inline>, LinkTarget),
+    /// An inline link target like `<target>`
+    InlineLinkTarget(Vec<Inline>),
+    /// Something tree sitter couldn't parse, kept as raw text
+    Error(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachedModifier {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Spoiler,
+    Superscript,
+    Subscript,
+    /// An inline comment like `%comment%`
+    InlineComment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub target: LinkTarget,
+    /// The explicit description, without one the target is shown
+    pub description: Option<Vec<Inline>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    Url(String),
+    /// A norg file like `{:$/notes/file:}`, optionally followed by a target inside of it
+    File {
+        path: String,
+        target: Option<Box<LinkTarget>>,
+    },
+    /// A non norg file like `{/ ~/file.pdf}`
+    ExternalFile(String),
+    LineNumber(String),
+    Heading {
+        level: usize,
+        title: Vec<Inline>,
+    },
+    /// A magic char link like `{# target}`, it matches inline link targets and headings
+    Generic(Vec<Inline>),
+    /// A wiki link like `{? target}`, it matches headings
+    Wiki(Vec<Inline>),
+    /// A target that isn't supported yet, kept as raw text
+    Unsupported(String),
+}
+
+/// The text of inlines without any formatting.
+///
+/// Links without a description show their target, the same way they are rendered. Inline
+/// comments are left out
+pub fn plain_text(inlines: &[Inline]) -> String {
+    let mut output = String::new();
+    push_plain_text(inlines, &mut output);
+    output
+}
+
+fn push_plain_text(inlines: &[Inline], output: &mut String) {
+    for inline in inlines {
+        match &inline.kind {
+            InlineKind::Text(text) | InlineKind::Verbatim(text) | InlineKind::Error(text) => {
+                output.push_str(text)
+            }
+            InlineKind::Space => output.push(' '),
+            InlineKind::SoftBreak => output.push('\n'),
+            InlineKind::Attached(AttachedModifier::InlineComment, _) => {}
+            InlineKind::Attached(_, content)
+            | InlineKind::AnchorDeclaration(content)
+            | InlineKind::AnchorDefinition(content, _)
+            | InlineKind::InlineLinkTarget(content) => push_plain_text(content, output),
+            InlineKind::Link(link) => match &link.description {
+                Some(description) => push_plain_text(description, output),
+                None => push_target_text(&link.target, output),
+            },
+        }
+    }
+}
+
+fn push_target_text(target: &LinkTarget, output: &mut String) {
+    match target {
+        LinkTarget::Url(text) | LinkTarget::ExternalFile(text) | LinkTarget::Unsupported(text) => {
+            output.push_str(text)
+        }
+        LinkTarget::File { path, target } => {
+            output.push_str(path);
+            if let Some(target) = target {
+                output.push(' ');
+                push_target_text(target, output);
+            }
+        }
+        LinkTarget::LineNumber(line) => {
+            output.push_str("Line ");
+            output.push_str(line);
+        }
+        LinkTarget::Heading { title: text, .. }
+        | LinkTarget::Generic(text)
+        | LinkTarget::Wiki(text) => push_plain_text(text, output),
+    }
+}
+
+/// A read only walk over a norg document.
+///
+/// Every method walks into the children by default, so an implementation only overrides the
+/// nodes it cares about and calls the matching `walk_` function to keep going. New node kinds
+/// only have to be added to the `walk_` functions
+pub trait Visit<'a> {
+    fn visit_block(&mut self, block: &'a Block) {
+        walk_block(self, block)
+    }
+
+    fn visit_inline(&mut self, inline: &'a Inline) {
+        walk_inline(self, inline)
+    }
+
+    fn visit_link_target(&mut self, target: &'a LinkTarget) {
+        walk_link_target(self, target)
+    }
+}
+
+pub fn walk_blocks<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, blocks: &'a [Block]) {
+    for block in blocks {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_inlines<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, inlines: &'a [Inline]) {
+    for inline in inlines {
+        visitor.visit_inline(inline);
+    }
+}
+
+pub fn walk_block<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, block: &'a Block) {
+    match &block.kind {
+        BlockKind::Paragraph(content) => walk_inlines(visitor, content),
+        BlockKind::Heading(heading) => {
+            walk_inlines(visitor, &heading.title);
+            walk_blocks(visitor, &heading.content);
+        }
+        BlockKind::List(items) | BlockKind::Quote(items) => {
+            for item in items {
+                walk_blocks(visitor, &item.content);
+            }
+        }
+        BlockKind::Carryover(_, target) => visitor.visit_block(target),
+        BlockKind::RangedTag(_)
+        | BlockKind::InfirmTag(_)
+        | BlockKind::HorizontalRule
+        | BlockKind::Unsupported(_)
+        | BlockKind::Error(_) => {}
+    }
+}
+
+pub fn walk_inline<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, inline: &'a Inline) {
+    match &inline.kind {
+        InlineKind::Attached(_, content)
+        | InlineKind::AnchorDeclaration(content)
+        | InlineKind::InlineLinkTarget(content) => walk_inlines(visitor, content),
+        InlineKind::AnchorDefinition(name, target) => {
+            walk_inlines(visitor, name);
+            visitor.visit_link_target(target);
+        }
+        InlineKind::Link(link) => {
+            visitor.visit_link_target(&link.target);
+            if let Some(description) = &link.description {
+                walk_inlines(visitor, description);
+            }
+        }
+        InlineKind::Text(_)
+        | InlineKind::Space
+        | InlineKind::SoftBreak
+        | InlineKind::Verbatim(_)
+        | InlineKind::Error(_) => {}
+    }
+}
+
+pub fn walk_link_target<'a, V: Visit<'a> + ?Sized>(visitor: &mut V, target: &'a LinkTarget) {
+    match target {
+        LinkTarget::File {
+            target: Some(target),
+            ..
+        } => visitor.visit_link_target(target),
+        LinkTarget::Heading { title: text, .. }
+        | LinkTarget::Generic(text)
+        | LinkTarget::Wiki(text) => walk_inlines(visitor, text),
+        LinkTarget::Url(_)
+        | LinkTarget::File { target: None, .. }
+        | LinkTarget::ExternalFile(_)
+        | LinkTarget::LineNumber(_)
+        | LinkTarget::Unsupported(_) => {}
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// The pandoc API version that's used when none is set, it's the one pandoc 3.1 uses
+pub const DEFAULT_API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// How to handle headings that are nested deeper than the six levels pandoc supports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeepHeadings {
+    /// Treat them like 6th level headings
+    #[default]
+    Clamp,
+    /// Replace the heading with a bold paragraph inside a `Div` with a `level-N` class
+    Div,
+    /// Shift all headings so that the shallowest one becomes a 1st level heading. Anything
+    /// that's still too deep is clamped
+    Rebase,
+}
+
+/// Which extension links to other norg files get
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LinkExtension {
+    /// The target format, so links keep working after the whole workspace is converted
+    #[default]
+    TargetFormat,
+    /// Keep the links without an extension
+    None,
+    /// A fixed extension, without the leading `.`
+    Custom(String),
+}
+
+/// How the ids of headings are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeadingIds {
+    /// The title followed by the level, e.g. `Some Heading2`
+    #[default]
+    TitleAndLevel,
+    /// A lowercase slug of the title like pandoc generates them, e.g. `some-heading`. Repeated
+    /// titles get a counter, e.g. `some-heading-1`
+    Slug,
+}
+
+/// What to do with inline comments (`%comment%`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Comments {
+    /// Leave them out of the output
+    #[default]
+    Drop,
+    /// Keep them in a `Span` with the `comment` class
+    Keep,
+}
+
+/// Everything that changes how a document is converted.
+///
+/// The defaults convert to html for the pandoc API version [`DEFAULT_API_VERSION`], so pandoc
+/// doesn't have to be asked for its version first
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    pub(crate) target_format: String,
+    pub(crate) api_version: Vec<u32>,
+    pub(crate) workspace_root: PathBuf,
+    /// Sorted, so options with the same workspaces always print the same
+    pub(crate) workspaces: BTreeMap<String, PathBuf>,
+    pub(crate) link_extension: LinkExtension,
+    pub(crate) heading_ids: HeadingIds,
+    pub(crate) comments: Comments,
+    pub(crate) deep_headings: DeepHeadings,
+    pub(crate) heading_offset: i64,
+    pub(crate) source_positions: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            target_format: String::from("html"),
+            api_version: DEFAULT_API_VERSION.to_vec(),
+            workspace_root: PathBuf::from("."),
+            workspaces: BTreeMap::new(),
+            link_extension: LinkExtension::default(),
+            heading_ids: HeadingIds::default(),
+            comments: Comments::default(),
+            deep_headings: DeepHeadings::default(),
+            heading_offset: 0,
+            source_positions: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// The pandoc format the output is meant for, links to other norg files get it as extension
+    pub fn target_format(mut self, target_format: impl Into<String>) -> Self {
+        self.target_format = target_format.into();
+        self
+    }
+
+    /// The pandoc API version written into the AST
+    pub fn api_version(mut self, api_version: Vec<u32>) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// The directory `$/` in links refers to
+    pub fn workspace_root(mut self, workspace_root: impl Into<PathBuf>) -> Self {
+        self.workspace_root = workspace_root.into();
+        self
+    }
+
+    /// Adds a named workspace that `$name/` in links refers to
+    pub fn workspace(mut self, name: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        self.workspaces.insert(name.into(), root.into());
+        self
+    }
+
+    pub fn link_extension(mut self, link_extension: LinkExtension) -> Self {
+        self.link_extension = link_extension;
+        self
+    }
+
+    pub fn heading_ids(mut self, heading_ids: HeadingIds) -> Self {
+        self.heading_ids = heading_ids;
+        self
+    }
+
+    pub fn comments(mut self, comments: Comments) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    pub fn deep_headings(mut self, deep_headings: DeepHeadings) -> Self {
+        self.deep_headings = deep_headings;
+        self
+    }
+
+    /// Shifts all heading levels by this amount, e.g. when embedding notes into a larger
+    /// document
+    pub fn heading_offset(mut self, heading_offset: i64) -> Self {
+        self.heading_offset = heading_offset;
+        self
+    }
+
+    /// Adds `data-pos="file@line:col-line:col"` attributes to the generated elements, the way
+    /// pandoc's `sourcepos` extension does. Paragraphs and list items are wrapped in a `Div` for
+    /// that, since pandoc has no attributes for them
+    pub fn source_positions(mut self, source_positions: bool) -> Self {
+        self.source_positions = source_positions;
+        self
+    }
+}
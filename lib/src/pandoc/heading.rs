@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+use pandoc_ast::{Attr, Block, Inline};
+
+use crate::{
+    norg::{self, BlockKind, Heading},
+    DeepHeadings, HeadingIds, Span,
+};
+
+use super::Lowering;
+
+/// The deepest heading level pandoc supports
+const MAX_LEVEL: i64 = 6;
+
+impl<'a> Lowering<'a> {
+    /// Lowers a heading into a `Div` holding the header and the content of the section
+    pub(super) fn heading(&mut self, heading: &'a Heading, span: &Span) -> Block {
+        let level = heading.level as i64 + self.heading_shift;
+
+        let mut text = match &heading.extension {
+            Some(extension) => self.extension(extension, &heading.line_span),
+            None => vec![],
+        };
+        text.append(&mut self.inlines(&heading.title));
+
+        let id = match self.targets.heading_id(span.byte_range.start) {
+            Some(id) => id.to_owned(),
+            None => id(
+                &heading.title,
+                heading.level,
+                self.options.heading_ids,
+                self.options.heading_offset,
+            ),
+        };
+        let mut content = vec![header(
+            self.options.deep_headings,
+            level,
+            (id, vec![], self.attributes(&heading.line_span)),
+            text,
+        )];
+        content.append(&mut self.blocks(&heading.content));
+
+        Block::Div(self.attr(String::default(), vec![], span), content)
+    }
+}
+
+/// Creates the block that introduces a section, applying the deep heading strategy
+fn header(deep_headings: DeepHeadings, level: i64, attr: Attr, text: Vec<Inline>) -> Block {
+    let level = level.max(1);
+    match deep_headings {
+        DeepHeadings::Div if level > MAX_LEVEL => {
+            let (id, mut classes, attributes) = attr;
+            classes.push(format!("level-{level}"));
+            Block::Div(
+                (id, classes, attributes),
+                vec![Block::Para(vec![Inline::Strong(text)])],
+            )
+        }
+        DeepHeadings::Clamp | DeepHeadings::Div | DeepHeadings::Rebase => {
+            Block::Header(level.min(MAX_LEVEL), attr, text)
+        }
+    }
+}
+
+/// Creates the id of a heading.
+///
+/// Tree sitter doesn't distinguish levels deeper than the 6th in links, so neither does the id.
+/// Only the heading offset shifts the level in the id, it's the same for every file. The shift
+/// of rebasing depends on the headings of the file, so links from other files couldn't match it
+pub(super) fn id(
+    title: &[norg::Inline],
+    level: usize,
+    style: HeadingIds,
+    heading_offset: i64,
+) -> String {
+    let title = norg::plain_text(title);
+    match style {
+        HeadingIds::TitleAndLevel => {
+            format!("{title}{}", link_level(level) as i64 + heading_offset)
+        }
+        HeadingIds::Slug => slug(&title),
+    }
+}
+
+/// Creates an id the way pandoc's `auto_identifiers` extension does
+fn slug(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+    let slug = slug.trim_start_matches(|c: char| !c.is_alphabetic());
+
+    if slug.is_empty() {
+        String::from("section")
+    } else {
+        slug.to_owned()
+    }
+}
+
+/// The level links to a heading use, tree sitter doesn't distinguish levels deeper than the 6th
+/// in links
+pub(super) fn link_level(level: usize) -> usize {
+    level.min(MAX_LEVEL as usize)
+}
+
+/// Makes a slug unique by appending the first free counter, the same way pandoc does
+pub(super) fn unique_slug(slug: String, used: &mut HashSet<String>) -> String {
+    let slug = if used.contains(&slug) {
+        (1..)
+            .map(|counter| format!("{slug}-{counter}"))
+            .find(|candidate| !used.contains(candidate))
+            .unwrap()
+    } else {
+        slug
+    };
+    used.insert(slug.clone());
+    slug
+}
+
+/// Finds the level of the shallowest heading in the document
+pub(super) fn shallowest_level(blocks: &[norg::Block]) -> Option<i64> {
+    blocks.iter().filter_map(heading_level).min()
+}
+
+/// The level of a heading, also behind the carryover tags in front of it
+fn heading_level(block: &norg::Block) -> Option<i64> {
+    match &block.kind {
+        BlockKind::Heading(heading) => Some(heading.level as i64),
+        BlockKind::Carryover(_, block) => heading_level(block),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_like_pandoc() {
+        assert_eq!(slug("Some Heading"), "some-heading");
+        assert_eq!(slug("What's new? (2023)"), "whats-new-2023");
+        assert_eq!(slug("1. Introduction"), "introduction");
+        assert_eq!(slug("file_name.rs"), "file_name.rs");
+        assert_eq!(slug("!!!"), "section");
+    }
+
+    #[test]
+    fn unique_slugs_get_a_counter() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_slug(String::from("notes"), &mut used), "notes");
+        assert_eq!(unique_slug(String::from("notes"), &mut used), "notes-1");
+        assert_eq!(unique_slug(String::from("notes-2"), &mut used), "notes-2");
+        assert_eq!(unique_slug(String::from("notes"), &mut used), "notes-3");
+    }
+
+    fn span() -> Span {
+        Span {
+            byte_range: 0..0,
+            line: 1,
+            column: 1,
+            end_line: 1,
+            end_column: 1,
+        }
+    }
+
+    fn heading(level: usize) -> norg::Block {
+        norg::Block {
+            kind: BlockKind::Heading(Heading {
+                level,
+                extension: None,
+                title: vec![],
+                line_span: span(),
+                content: vec![],
+            }),
+            span: span(),
+        }
+    }
+
+    #[test]
+    fn shallowest_level_looks_behind_carryover_tags() {
+        let id = norg::Tag {
+            name: String::from("id"),
+            parameters: vec![String::from("intro")],
+            content: String::new(),
+            span: span(),
+        };
+        let blocks = vec![
+            heading(3),
+            norg::Block {
+                kind: BlockKind::Carryover(vec![id], Box::new(heading(2))),
+                span: span(),
+            },
+        ];
+        assert_eq!(shallowest_level(&blocks), Some(2));
+    }
+}
@@ -0,0 +1,116 @@
+use pandoc_ast::Inline;
+
+use crate::{
+    norg::{self, AttachedModifier, DetachedExtension, InlineKind, TodoStatus},
+    Comments, Span,
+};
+
+use super::Lowering;
+
+impl<'a> Lowering<'a> {
+    pub(super) fn inlines(&mut self, inlines: &'a [norg::Inline]) -> Vec<Inline> {
+        let mut output = Vec::with_capacity(inlines.len());
+        for inline in inlines {
+            if let Some(inline) = self.inline(inline) {
+                output.push(inline);
+            }
+        }
+        output
+    }
+
+    fn inline(&mut self, inline: &'a norg::Inline) -> Option<Inline> {
+        let inline = match &inline.kind {
+            InlineKind::Text(text) => Inline::Str(text.clone()),
+
+            InlineKind::Space => Inline::Space,
+
+            InlineKind::SoftBreak => Inline::SoftBreak,
+
+            InlineKind::Attached(AttachedModifier::InlineComment, _)
+                if self.options.comments == Comments::Drop =>
+            {
+                return None
+            }
+
+            InlineKind::Attached(modifier, content) => {
+                let content = self.inlines(content);
+                match modifier {
+                    AttachedModifier::Bold => Inline::Strong(content),
+                    AttachedModifier::Italic => Inline::Emph(content),
+                    AttachedModifier::Underline => Inline::Underline(content),
+                    AttachedModifier::Strikethrough => Inline::Strikeout(content),
+                    AttachedModifier::Spoiler => Inline::Span(
+                        self.attr(
+                            String::default(),
+                            vec![String::from("spoiler")],
+                            &inline.span,
+                        ),
+                        content,
+                    ),
+                    AttachedModifier::Superscript => Inline::Superscript(content),
+                    AttachedModifier::Subscript => Inline::Subscript(content),
+                    AttachedModifier::InlineComment => Inline::Span(
+                        self.attr(
+                            String::default(),
+                            vec![String::from("comment")],
+                            &inline.span,
+                        ),
+                        content,
+                    ),
+                }
+            }
+
+            InlineKind::Verbatim(content) => {
+                Inline::Code((String::default(), vec![], vec![]), content.clone())
+            }
+
+            InlineKind::Link(link) => self.link(link, &inline.span),
+
+            InlineKind::AnchorDeclaration(name) => self.anchor_declaration(name, &inline.span),
+
+            InlineKind::AnchorDefinition(name, target) => {
+                let description = self.inlines(name);
+                let (target, _) = self.link_target(target, &inline.span);
+                Inline::Link((String::default(), vec![], vec![]), description, target)
+            }
+
+            InlineKind::InlineLinkTarget(content) => {
+                let id = format!("{} inl", norg::plain_text(content));
+                Inline::Span(self.attr(id, vec![], &inline.span), self.inlines(content))
+            }
+
+            InlineKind::Error(raw) => Inline::Span(
+                self.attr(
+                    String::new(),
+                    vec![String::from("parse-error")],
+                    &inline.span,
+                ),
+                vec![Inline::Str(raw.clone())],
+            ),
+        };
+
+        Some(inline)
+    }
+
+    /// Lowers a detached modifier extension, followed by a space
+    pub(super) fn extension(&mut self, extension: &DetachedExtension, span: &Span) -> Vec<Inline> {
+        match extension {
+            DetachedExtension::Todo(TodoStatus::Urgent) => vec![
+                Inline::Strong(vec![Inline::Span(
+                    self.attr(
+                        String::default(),
+                        vec!["todo".to_string(), "urgent".to_string()],
+                        span,
+                    ),
+                    vec![Inline::Str(TodoStatus::Urgent.marker().to_string())],
+                )]),
+                Inline::Space,
+            ],
+            DetachedExtension::Todo(status) => {
+                self.warn(span, "not supported yet, keeping the raw text");
+                vec![Inline::Str(status.marker().to_owned()), Inline::Space]
+            }
+            DetachedExtension::Unsupported(raw) => vec![Inline::Str(raw.clone()), Inline::Space],
+        }
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+
+use pandoc_ast::Inline;
+
+use crate::{
+    norg::{self, BlockKind, InlineKind, Link, LinkTarget, Visit},
+    AmbiguousLink, BrokenLink, DuplicateTarget, FileLink, HeadingIds, LinkExtension, Span,
+    TargetKind,
+};
+
+use super::{heading, Lowering};
+
+/// Something a link can point to
+struct Target<'a> {
+    /// The id of the element, or the target of an anchor definition
+    destination: Destination<'a>,
+    /// The level of a heading, as far as links can tell it apart
+    level: Option<usize>,
+    span: &'a Span,
+}
+
+enum Destination<'a> {
+    Id(String),
+    Link(&'a LinkTarget),
+}
+
+/// Everything in a document that magic char, wiki, heading and anchor links can point to
+pub(super) struct Targets<'a> {
+    heading_ids: HeadingIds,
+    heading_offset: i64,
+    /// The inline link targets by their text
+    inline_targets: HashMap<String, Vec<Target<'a>>>,
+    /// The headings by their title
+    headings: HashMap<String, Vec<Target<'a>>>,
+    /// The anchor definitions by their name
+    anchors: HashMap<String, Vec<Target<'a>>>,
+    /// The ids of the headings by the byte their block starts at, slugs are already made unique
+    heading_ids_by_start: HashMap<usize, String>,
+    /// The slugs given to headings so far
+    slugs: HashSet<String>,
+    /// Targets that were defined more than once, in document order
+    pub(super) duplicates: Vec<DuplicateTarget>,
+}
+
+impl<'a> Targets<'a> {
+    pub(super) fn collect(
+        blocks: &'a [norg::Block],
+        heading_ids: HeadingIds,
+        heading_offset: i64,
+    ) -> Self {
+        let mut targets = Targets {
+            heading_ids,
+            heading_offset,
+            inline_targets: HashMap::new(),
+            headings: HashMap::new(),
+            anchors: HashMap::new(),
+            heading_ids_by_start: HashMap::new(),
+            slugs: HashSet::new(),
+            duplicates: vec![],
+        };
+        norg::walk_blocks(&mut targets, blocks);
+        targets
+    }
+
+    /// The id of the heading whose block starts at that byte
+    pub(super) fn heading_id(&self, start: usize) -> Option<&str> {
+        self.heading_ids_by_start.get(&start).map(String::as_str)
+    }
+
+    /// Adds a target, reporting it if one of the same kind already has that name.
+    ///
+    /// Headings only clash with headings of the same level, since their ids differ otherwise
+    fn define(&mut self, kind: TargetKind, name: String, target: Target<'a>) {
+        let map = match kind {
+            TargetKind::Heading => &mut self.headings,
+            TargetKind::InlineLinkTarget => &mut self.inline_targets,
+            TargetKind::Anchor => &mut self.anchors,
+        };
+        let defined = map.entry(name.clone()).or_default();
+        if let Some(first) = defined.iter().find(|defined| defined.level == target.level) {
+            self.duplicates.push(DuplicateTarget {
+                kind,
+                name,
+                span: target.span.clone(),
+                first: first.span.clone(),
+            });
+        }
+        defined.push(target);
+    }
+}
+
+impl<'a> Visit<'a> for Targets<'a> {
+    fn visit_block(&mut self, block: &'a norg::Block) {
+        if let BlockKind::Heading(heading) = &block.kind {
+            let mut id = heading::id(
+                &heading.title,
+                heading.level,
+                self.heading_ids,
+                self.heading_offset,
+            );
+            if self.heading_ids == HeadingIds::Slug {
+                id = heading::unique_slug(id, &mut self.slugs);
+            }
+            self.heading_ids_by_start
+                .insert(block.span.byte_range.start, id.clone());
+            self.define(
+                TargetKind::Heading,
+                norg::plain_text(&heading.title),
+                Target {
+                    destination: Destination::Id(id),
+                    level: Some(heading::link_level(heading.level)),
+                    span: &block.span,
+                },
+            );
+        }
+        norg::walk_block(self, block);
+    }
+
+    fn visit_inline(&mut self, inline: &'a norg::Inline) {
+        match &inline.kind {
+            InlineKind::InlineLinkTarget(content) => {
+                let text = norg::plain_text(content);
+                let id = format!("{text} inl");
+                self.define(
+                    TargetKind::InlineLinkTarget,
+                    text,
+                    Target {
+                        destination: Destination::Id(id),
+                        level: None,
+                        span: &inline.span,
+                    },
+                );
+            }
+            InlineKind::AnchorDefinition(name, target) => {
+                self.define(
+                    TargetKind::Anchor,
+                    norg::plain_text(name),
+                    Target {
+                        destination: Destination::Link(target),
+                        level: None,
+                        span: &inline.span,
+                    },
+                );
+            }
+            _ => {}
+        }
+        norg::walk_inline(self, inline);
+    }
+}
+
+impl<'a> Lowering<'a> {
+    pub(super) fn link(&mut self, link: &'a Link, span: &Span) -> Inline {
+        let (target, default_description) = self.link_target(&link.target, span);
+        let description = match &link.description {
+            Some(description) => self.inlines(description),
+            None => default_description,
+        };
+
+        Inline::Link((String::default(), vec![], vec![]), description, target)
+    }
+
+    /// Links an anchor to the target of the definition with the same name
+    pub(super) fn anchor_declaration(&mut self, name: &'a [norg::Inline], span: &Span) -> Inline {
+        let description = self.inlines(name);
+
+        let key = norg::plain_text(name);
+        let target = match self.resolve(&[TargetKind::Anchor], &key, None, span) {
+            Some(Destination::Link(target)) => self.link_target(target, span).0,
+            Some(Destination::Id(id)) => (format!("#{id}"), String::new()),
+            None => (String::new(), String::new()),
+        };
+
+        Inline::Link((String::default(), vec![], vec![]), description, target)
+    }
+
+    /// Looks up the targets of the given kinds with that name and records broken and ambiguous
+    /// links in the report. Broken links are only reported there, not as warnings too.
+    ///
+    /// With a level only headings of that level match. Of several matches the first one wins,
+    /// the same way a browser picks the first element with an id
+    fn resolve(
+        &mut self,
+        kinds: &[TargetKind],
+        key: &str,
+        level: Option<usize>,
+        span: &Span,
+    ) -> Option<Destination<'a>> {
+        let mut candidates = vec![];
+        for kind in kinds {
+            let map = match kind {
+                TargetKind::Heading => &self.targets.headings,
+                TargetKind::InlineLinkTarget => &self.targets.inline_targets,
+                TargetKind::Anchor => &self.targets.anchors,
+            };
+            candidates.extend(map.get(key).into_iter().flatten().filter(|target| {
+                level.is_none() || target.level == level.map(heading::link_level)
+            }));
+        }
+
+        let Some(first) = candidates.first() else {
+            self.links.broken.push(BrokenLink {
+                target: key.to_owned(),
+                span: span.clone(),
+            });
+            return None;
+        };
+        let destination = match &first.destination {
+            Destination::Id(id) => Destination::Id(id.clone()),
+            Destination::Link(target) => Destination::Link(target),
+        };
+
+        if candidates.len() > 1 {
+            let candidates: Vec<Span> = candidates
+                .iter()
+                .map(|target| target.span.clone())
+                .collect();
+            self.warn(
+                span,
+                format!(
+                    "the link target {key} matches {} targets, using the one at {}:{}",
+                    candidates.len(),
+                    candidates[0].line,
+                    candidates[0].column
+                ),
+            );
+            self.links.ambiguous.push(AmbiguousLink {
+                target: key.to_owned(),
+                span: span.clone(),
+                candidates,
+            });
+        }
+
+        Some(destination)
+    }
+
+    /// Resolves a link target, returns it together with the description to use when the link
+    /// doesn't have one
+    pub(super) fn link_target(
+        &mut self,
+        target: &'a LinkTarget,
+        span: &Span,
+    ) -> ((String, String), Vec<Inline>) {
+        match target {
+            LinkTarget::Url(url) => ((url.clone(), String::new()), vec![Inline::Str(url.clone())]),
+
+            LinkTarget::File { path, target } => {
+                let mut file = self.workspace_path(path);
+                self.links.files.push(FileLink {
+                    path: path.clone(),
+                    resolved: file.clone(),
+                    span: span.clone(),
+                });
+                let mut description = vec![Inline::Str(file.clone())];
+                match &self.options.link_extension {
+                    LinkExtension::TargetFormat => {
+                        file.push_str(&format!(".{}", self.options.target_format))
+                    }
+                    LinkExtension::None => {}
+                    LinkExtension::Custom(extension) => file.push_str(&format!(".{extension}")),
+                }
+
+                if let Some(target) = target {
+                    let (sub_target, mut sub_description) = self.foreign_target(target, span);
+                    file.push_str(&sub_target);
+                    description.push(Inline::Space);
+                    description.append(&mut sub_description);
+                }
+
+                ((file, String::new()), description)
+            }
+
+            LinkTarget::ExternalFile(file) => {
+                let file = match dirs::home_dir() {
+                    Some(home_dir) => file.replace('~', &home_dir.to_string_lossy()),
+                    None => {
+                        if file.contains('~') {
+                            self.warn(span, "couldn't find the home directory");
+                        }
+                        file.clone()
+                    }
+                };
+
+                ((file.clone(), String::new()), vec![Inline::Str(file)])
+            }
+
+            LinkTarget::LineNumber(line_number) => (
+                (format!("#L{line_number}"), String::new()),
+                vec![Inline::Str(format!("Line {line_number}"))],
+            ),
+
+            LinkTarget::Heading { level, title } => {
+                let key = norg::plain_text(title);
+                let heading_id =
+                    match self.resolve(&[TargetKind::Heading], &key, Some(*level), span) {
+                        Some(Destination::Id(id)) => id,
+                        _ => heading::id(
+                            title,
+                            *level,
+                            self.options.heading_ids,
+                            self.options.heading_offset,
+                        ),
+                    };
+
+                (
+                    (format!("#{heading_id}"), String::new()),
+                    self.inlines(title),
+                )
+            }
+
+            LinkTarget::Generic(text) => {
+                let key = norg::plain_text(text);
+                let kinds = [TargetKind::InlineLinkTarget, TargetKind::Heading];
+                let target = match self.resolve(&kinds, &key, None, span) {
+                    Some(Destination::Id(id)) => format!("#{id}"),
+                    _ => format!("#{key}"),
+                };
+
+                ((target, String::new()), self.inlines(text))
+            }
+
+            LinkTarget::Wiki(text) => {
+                let key = norg::plain_text(text);
+                let target = match self.resolve(&[TargetKind::Heading], &key, None, span) {
+                    Some(Destination::Id(id)) => format!("#{id}"),
+                    _ => format!("#{key}"),
+                };
+
+                ((target, String::new()), self.inlines(text))
+            }
+
+            LinkTarget::Unsupported(raw) => {
+                ((raw.clone(), String::new()), vec![Inline::Str(raw.clone())])
+            }
+        }
+    }
+
+    /// Resolves a target inside another file, its headings and link targets aren't known here.
+    ///
+    /// Heading ids come from the title alone. When the other file has several headings with that
+    /// title, the counter it adds to the later slugs is missing, so the link goes to the first
+    fn foreign_target(&mut self, target: &'a LinkTarget, span: &Span) -> (String, Vec<Inline>) {
+        match target {
+            LinkTarget::Heading { level, title } => {
+                let heading_id = heading::id(
+                    title,
+                    *level,
+                    self.options.heading_ids,
+                    self.options.heading_offset,
+                );
+                (format!("#{heading_id}"), self.inlines(title))
+            }
+            LinkTarget::Generic(text) | LinkTarget::Wiki(text) => {
+                (format!("#{}", norg::plain_text(text)), self.inlines(text))
+            }
+            _ => {
+                let ((href, _), description) = self.link_target(target, span);
+                (href, description)
+            }
+        }
+    }
+
+    /// Resolves a path starting with `$/` or `$name/` against the workspaces
+    fn workspace_path(&self, path: &str) -> String {
+        let Some(path) = path.strip_prefix('$') else {
+            return path.to_owned();
+        };
+
+        let (name, rest) = path.split_at(path.find('/').unwrap_or(path.len()));
+        let root = if name.is_empty() {
+            &self.options.workspace_root
+        } else if let Some(root) = self.options.workspaces.get(name) {
+            root
+        } else {
+            return format!("{}{path}", self.options.workspace_root.to_string_lossy());
+        };
+
+        format!("{}{rest}", root.to_string_lossy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        norg::{Block, Document, InlineKind},
+        pandoc, ParseOptions,
+    };
+
+    use super::*;
+
+    fn span() -> Span {
+        Span {
+            byte_range: 0..0,
+            line: 1,
+            column: 1,
+            end_line: 1,
+            end_column: 1,
+        }
+    }
+
+    fn text(text: &str) -> norg::Inline {
+        norg::Inline {
+            kind: InlineKind::Text(text.to_owned()),
+            span: span(),
+        }
+    }
+
+    #[test]
+    fn headings_in_other_files_get_the_id_of_their_title() {
+        let link = norg::Inline {
+            kind: InlineKind::Link(Link {
+                target: LinkTarget::File {
+                    path: String::from("other"),
+                    target: Some(Box::new(LinkTarget::Heading {
+                        level: 1,
+                        title: vec![text("Intro")],
+                    })),
+                },
+                description: Some(vec![text("second intro")]),
+            }),
+            span: span(),
+        };
+        let document = Document {
+            content: vec![Block {
+                kind: BlockKind::Paragraph(vec![link]),
+                span: span(),
+            }],
+            ..Document::default()
+        };
+
+        let options = ParseOptions::new().heading_ids(HeadingIds::Slug);
+        let parsed = pandoc::lower(&document, &options);
+        let [pandoc_ast::Block::Para(inlines)] = parsed.ast.blocks.as_slice() else {
+            panic!("expected a paragraph, got {:?}", parsed.ast.blocks);
+        };
+        let [Inline::Link(_, _, (href, _))] = inlines.as_slice() else {
+            panic!("expected a link, got {inlines:?}");
+        };
+        // Even if it's the second heading called Intro there, which pandoc calls intro-1
+        assert_eq!(href, "other.html#intro");
+    }
+}
@@ -0,0 +1,451 @@
+use pandoc_ast::{Block, ListNumberDelim, ListNumberStyle};
+
+use crate::{
+    norg::{Nestable, NestableKind, Tag},
+    Span,
+};
+
+use super::Lowering;
+
+const DEFAULT_ORDERED_ATTR: (i64, ListNumberStyle, ListNumberDelim) = (
+    1,
+    ListNumberStyle::DefaultStyle,
+    ListNumberDelim::DefaultDelim,
+);
+
+/// The ordered list attributes per nesting level.
+///
+/// They are taken from the `list-start` and `list-style` document metadata and can be
+/// overridden for a single list with the `#start` and `#style` carryover tags. Every parameter
+/// applies to one nesting level, so `#style 1. a) i.` styles the first three levels. Starts that
+/// aren't numbers are warned about and start at 1
+#[derive(Default)]
+struct NumberFormat {
+    starts: Vec<i64>,
+    styles: Vec<(ListNumberStyle, ListNumberDelim)>,
+}
+
+impl NumberFormat {
+    /// The format of the list at `span`, warnings about the metadata are reported there
+    fn new(lowering: &mut Lowering, carryover_tags: &[&Tag], span: &Span) -> Self {
+        let mut format = NumberFormat::default();
+
+        let metadata = lowering.metadata;
+        if let Some(starts) = metadata.get("list-start") {
+            for start in format.set_starts(starts.split_whitespace()) {
+                lowering.warn(
+                    span,
+                    format!("the list-start metadata {start} isn't a number, starting at 1"),
+                );
+            }
+        }
+        if let Some(styles) = metadata.get("list-style") {
+            format.set_styles(styles.split_whitespace());
+        }
+
+        for tag in carryover_tags {
+            match tag.name.as_str() {
+                "start" => {
+                    for start in format.set_starts(tag.parameters.iter().map(String::as_str)) {
+                        lowering.warn(
+                            &tag.span,
+                            format!("the start {start} isn't a number, starting at 1"),
+                        );
+                    }
+                }
+                "style" => format.set_styles(tag.parameters.iter().map(String::as_str)),
+                _ => {}
+            }
+        }
+
+        format
+    }
+
+    /// Sets the start of every level, returns the starts that aren't numbers
+    fn set_starts<'a>(&mut self, starts: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+        let mut invalid = vec![];
+        self.starts = starts
+            .map(|start| {
+                start.parse().unwrap_or_else(|_| {
+                    invalid.push(start);
+                    1
+                })
+            })
+            .collect();
+        invalid
+    }
+
+    fn set_styles<'a>(&mut self, styles: impl Iterator<Item = &'a str>) {
+        self.styles = styles.map(parse_number_style).collect();
+    }
+
+    fn attributes(&self, nesting: usize) -> (i64, ListNumberStyle, ListNumberDelim) {
+        let start = self.starts.get(nesting - 1).copied().unwrap_or(1);
+        let (style, delim) = self
+            .styles
+            .get(nesting - 1)
+            .cloned()
+            .unwrap_or((DEFAULT_ORDERED_ATTR.1, DEFAULT_ORDERED_ATTR.2));
+        (start, style, delim)
+    }
+}
+
+/// Parses either a style name like `lower-roman` or an example marker like `a)` or `(i)`
+fn parse_number_style(style: &str) -> (ListNumberStyle, ListNumberDelim) {
+    let named = match style {
+        "decimal" | "arabic" => Some(ListNumberStyle::Decimal),
+        "lower-alpha" | "alpha" => Some(ListNumberStyle::LowerAlpha),
+        "upper-alpha" => Some(ListNumberStyle::UpperAlpha),
+        "lower-roman" | "roman" => Some(ListNumberStyle::LowerRoman),
+        "upper-roman" => Some(ListNumberStyle::UpperRoman),
+        _ => None,
+    };
+    if let Some(named) = named {
+        return (named, ListNumberDelim::DefaultDelim);
+    }
+
+    let (marker, delim) = if let Some(marker) = style
+        .strip_prefix('(')
+        .and_then(|style| style.strip_suffix(')'))
+    {
+        (marker, ListNumberDelim::TwoParens)
+    } else if let Some(marker) = style.strip_suffix(')') {
+        (marker, ListNumberDelim::OneParen)
+    } else if let Some(marker) = style.strip_suffix('.') {
+        (marker, ListNumberDelim::Period)
+    } else {
+        (style, ListNumberDelim::DefaultDelim)
+    };
+
+    let style = match marker {
+        "1" => ListNumberStyle::Decimal,
+        "a" => ListNumberStyle::LowerAlpha,
+        "A" => ListNumberStyle::UpperAlpha,
+        "i" => ListNumberStyle::LowerRoman,
+        "I" => ListNumberStyle::UpperRoman,
+        _ => ListNumberStyle::DefaultStyle,
+    };
+
+    (style, delim)
+}
+
+/// A single list item with its lowered content
+struct Item {
+    kind: NestableKind,
+    nesting: usize,
+    content: Vec<Block>,
+}
+
+/// A list that is still being built, one per nesting level
+struct OpenList {
+    kind: NestableKind,
+    nesting: usize,
+    items: Vec<Vec<Block>>,
+}
+
+impl OpenList {
+    fn into_block(self, format: &NumberFormat) -> Block {
+        match self.kind {
+            NestableKind::Ordered => {
+                Block::OrderedList(format.attributes(self.nesting), self.items)
+            }
+            NestableKind::Unordered | NestableKind::Quote => Block::BulletList(self.items),
+        }
+    }
+}
+
+impl<'a> Lowering<'a> {
+    /// Lowers the items of a list into `output`.
+    ///
+    /// This can push more than one list, as pandoc lists can't switch between ordered and
+    /// unordered items on the same level
+    pub(super) fn list(
+        &mut self,
+        items: &'a [Nestable],
+        carryover_tags: &[&Tag],
+        span: &Span,
+        output: &mut Vec<Block>,
+    ) {
+        let format = NumberFormat::new(self, carryover_tags, span);
+
+        let items = items
+            .iter()
+            .map(|item| {
+                let content = self.nestable_content(item);
+                Item {
+                    kind: item.kind,
+                    nesting: item.level,
+                    content: self.positioned(content, &item.span),
+                }
+            })
+            .collect();
+
+        build_lists(items, &format, output);
+    }
+}
+
+/// Builds the list tree from the flat items.
+///
+/// Every nesting level keeps track of its own kind. Skipped levels get a wrapper item of the
+/// same kind as the item that skipped them
+fn build_lists(items: Vec<Item>, format: &NumberFormat, output: &mut Vec<Block>) {
+    let mut lists: Vec<OpenList> = vec![];
+
+    for item in items {
+        while lists.last().map_or(false, |list| {
+            list.nesting > item.nesting || (list.nesting == item.nesting && list.kind != item.kind)
+        }) {
+            close_list(&mut lists, output, format);
+        }
+
+        let open_from = lists.last().map_or(1, |list| list.nesting + 1);
+        for nesting in open_from..item.nesting {
+            lists.push(OpenList {
+                kind: item.kind,
+                nesting,
+                items: vec![vec![]],
+            });
+        }
+        if open_from <= item.nesting {
+            lists.push(OpenList {
+                kind: item.kind,
+                nesting: item.nesting,
+                items: vec![],
+            });
+        }
+
+        lists.last_mut().unwrap().items.push(item.content);
+    }
+
+    while !lists.is_empty() {
+        close_list(&mut lists, output, format);
+    }
+}
+
+/// Closes the innermost open list and attaches it to the last item of its parent
+fn close_list(lists: &mut Vec<OpenList>, output: &mut Vec<Block>, format: &NumberFormat) {
+    let block = lists.pop().unwrap().into_block(format);
+    match lists.last_mut() {
+        Some(parent) => parent.items.last_mut().unwrap().push(block),
+        None => output.push(block),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pandoc_ast::Inline;
+
+    use crate::{
+        norg::{self, BlockKind, Document, InlineKind},
+        pandoc, ParseOptions,
+    };
+
+    use super::*;
+
+    #[test]
+    fn parses_style_names() {
+        assert_eq!(
+            parse_number_style("lower-roman"),
+            (ListNumberStyle::LowerRoman, ListNumberDelim::DefaultDelim)
+        );
+        assert_eq!(
+            parse_number_style("alpha"),
+            (ListNumberStyle::LowerAlpha, ListNumberDelim::DefaultDelim)
+        );
+    }
+
+    #[test]
+    fn parses_example_markers() {
+        assert_eq!(
+            parse_number_style("1."),
+            (ListNumberStyle::Decimal, ListNumberDelim::Period)
+        );
+        assert_eq!(
+            parse_number_style("a)"),
+            (ListNumberStyle::LowerAlpha, ListNumberDelim::OneParen)
+        );
+        assert_eq!(
+            parse_number_style("(I)"),
+            (ListNumberStyle::UpperRoman, ListNumberDelim::TwoParens)
+        );
+        assert_eq!(
+            parse_number_style("A"),
+            (ListNumberStyle::UpperAlpha, ListNumberDelim::DefaultDelim)
+        );
+    }
+
+    #[test]
+    fn unknown_styles_are_the_default() {
+        assert_eq!(
+            parse_number_style("x."),
+            (ListNumberStyle::DefaultStyle, ListNumberDelim::Period)
+        );
+        assert_eq!(
+            parse_number_style("default"),
+            (ListNumberStyle::DefaultStyle, ListNumberDelim::DefaultDelim)
+        );
+    }
+
+    #[test]
+    fn starts_that_are_no_numbers_start_at_one() {
+        let mut format = NumberFormat::default();
+        assert_eq!(format.set_starts(["3", "x", "2"].into_iter()), ["x"]);
+        assert_eq!(format.starts, [3, 1, 2]);
+    }
+
+    fn item(kind: NestableKind, nesting: usize, text: &str) -> Item {
+        Item {
+            kind,
+            nesting,
+            content: vec![text_block(text)],
+        }
+    }
+
+    fn text_block(text: &str) -> Block {
+        Block::Plain(vec![Inline::Str(text.to_owned())])
+    }
+
+    fn lists(items: Vec<Item>, format: &NumberFormat) -> Vec<Block> {
+        let mut output = vec![];
+        build_lists(items, format, &mut output);
+        output
+    }
+
+    #[test]
+    fn nests_items_by_level() {
+        let items = vec![
+            item(NestableKind::Unordered, 1, "a"),
+            item(NestableKind::Ordered, 2, "b"),
+            item(NestableKind::Ordered, 2, "c"),
+            item(NestableKind::Unordered, 1, "d"),
+        ];
+        assert_eq!(
+            lists(items, &NumberFormat::default()),
+            [Block::BulletList(vec![
+                vec![
+                    text_block("a"),
+                    Block::OrderedList(
+                        DEFAULT_ORDERED_ATTR,
+                        vec![vec![text_block("b")], vec![text_block("c")]]
+                    ),
+                ],
+                vec![text_block("d")],
+            ])]
+        );
+    }
+
+    #[test]
+    fn switching_the_kind_starts_a_new_list() {
+        let items = vec![
+            item(NestableKind::Unordered, 1, "a"),
+            item(NestableKind::Ordered, 1, "b"),
+        ];
+        assert_eq!(
+            lists(items, &NumberFormat::default()),
+            [
+                Block::BulletList(vec![vec![text_block("a")]]),
+                Block::OrderedList(DEFAULT_ORDERED_ATTR, vec![vec![text_block("b")]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_levels_get_a_wrapper_item() {
+        let items = vec![item(NestableKind::Ordered, 2, "a")];
+        assert_eq!(
+            lists(items, &NumberFormat::default()),
+            [Block::OrderedList(
+                DEFAULT_ORDERED_ATTR,
+                vec![vec![Block::OrderedList(
+                    DEFAULT_ORDERED_ATTR,
+                    vec![vec![text_block("a")]]
+                )]]
+            )]
+        );
+    }
+
+    #[test]
+    fn ordered_lists_use_the_format_of_their_level() {
+        let format = NumberFormat {
+            starts: vec![3],
+            styles: vec![
+                (ListNumberStyle::Decimal, ListNumberDelim::Period),
+                (ListNumberStyle::LowerAlpha, ListNumberDelim::OneParen),
+            ],
+        };
+        let items = vec![
+            item(NestableKind::Ordered, 1, "a"),
+            item(NestableKind::Ordered, 2, "b"),
+        ];
+        assert_eq!(
+            lists(items, &format),
+            [Block::OrderedList(
+                (3, ListNumberStyle::Decimal, ListNumberDelim::Period),
+                vec![vec![
+                    text_block("a"),
+                    Block::OrderedList(
+                        (1, ListNumberStyle::LowerAlpha, ListNumberDelim::OneParen),
+                        vec![vec![text_block("b")]]
+                    ),
+                ]]
+            )]
+        );
+    }
+
+    #[test]
+    fn items_on_the_second_level_keep_all_their_blocks() {
+        let span = || Span {
+            byte_range: 0..0,
+            line: 1,
+            column: 1,
+            end_line: 1,
+            end_column: 1,
+        };
+        let paragraph = |text: &str| norg::Block {
+            kind: BlockKind::Paragraph(vec![norg::Inline {
+                kind: InlineKind::Text(text.to_owned()),
+                span: span(),
+            }]),
+            span: span(),
+        };
+        let code = norg::Block {
+            kind: BlockKind::RangedTag(Tag {
+                name: String::from("code"),
+                parameters: vec![String::from("rust")],
+                content: String::from("let a = 1;\n"),
+                span: span(),
+            }),
+            span: span(),
+        };
+        // What tree sitter makes of `-- item`, the code block and the paragraph after it
+        let document = Document {
+            content: vec![norg::Block {
+                kind: BlockKind::List(vec![Nestable {
+                    kind: NestableKind::Unordered,
+                    level: 2,
+                    extension: None,
+                    content: vec![paragraph("item"), code, paragraph("more")],
+                    span: span(),
+                }]),
+                span: span(),
+            }],
+            ..Document::default()
+        };
+
+        let parsed = pandoc::lower(&document, &ParseOptions::new());
+        let para = |text: &str| Block::Para(vec![Inline::Str(text.to_owned())]);
+        assert_eq!(
+            parsed.ast.blocks,
+            [Block::BulletList(vec![vec![Block::BulletList(vec![
+                vec![
+                    para("item"),
+                    Block::CodeBlock(
+                        (String::new(), vec![String::from("rust")], vec![]),
+                        String::from("let a = 1;\n")
+                    ),
+                    para("more"),
+                ]
+            ])]])]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Lowers the typed norg representation into a pandoc AST
+
+use std::collections::BTreeMap;
+
+use pandoc_ast::{Attr, Block, Inline, Map, MetaValue, Pandoc};
+
+use crate::{
+    norg::{self, BlockKind, Nestable, Tag},
+    DeepHeadings, Diagnostic, LinkReport, ParseOptions, Parsed, Span,
+};
+
+mod heading;
+mod inline;
+mod link;
+mod list;
+mod quote;
+mod table;
+mod tags;
+
+struct Lowering<'a> {
+    /// The name of the file, for the source positions
+    name: Option<&'a str>,
+    metadata: &'a BTreeMap<String, String>,
+    options: &'a ParseOptions,
+    carryover_tags: Vec<&'a Tag>,
+    heading_shift: i64,
+    targets: link::Targets<'a>,
+    links: LinkReport,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Lowering<'a> {
+    /// Records a warning about the element at `span`
+    fn warn(&mut self, span: &Span, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic::warning_at(span, message));
+    }
+
+    /// The key value attributes of an element, its source position if those are wanted
+    fn attributes(&self, span: &Span) -> Vec<(String, String)> {
+        if !self.options.source_positions {
+            return vec![];
+        }
+
+        let range = format!(
+            "{}:{}-{}:{}",
+            span.line, span.column, span.end_line, span.end_column
+        );
+        let position = match self.name {
+            Some(name) => format!("{name}@{range}"),
+            None => range,
+        };
+        vec![(String::from("data-pos"), position)]
+    }
+
+    fn attr(&self, id: String, classes: Vec<String>, span: &Span) -> Attr {
+        (id, classes, self.attributes(span))
+    }
+
+    /// Wraps blocks that can't have attributes in a `Div` with the source position
+    fn positioned(&self, content: Vec<Block>, span: &Span) -> Vec<Block> {
+        if self.options.source_positions {
+            vec![Block::Div(self.attr(String::new(), vec![], span), content)]
+        } else {
+            content
+        }
+    }
+
+    fn blocks(&mut self, blocks: &'a [norg::Block]) -> Vec<Block> {
+        let mut output = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            self.block(block, &mut output);
+        }
+        output
+    }
+
+    /// Lowers a single block into `output`, some blocks (e.g. lists) turn into several
+    fn block(&mut self, block: &'a norg::Block, output: &mut Vec<Block>) {
+        // Carryover tags only apply to the block right after them, not to the blocks inside it
+        let carryover_tags = std::mem::take(&mut self.carryover_tags);
+
+        let block = match &block.kind {
+            BlockKind::Paragraph(content) => {
+                let paragraph = Block::Para(self.inlines(content));
+                output.append(&mut self.positioned(vec![paragraph], &block.span));
+                return;
+            }
+
+            BlockKind::Heading(heading) => self.heading(heading, &block.span),
+
+            BlockKind::List(items) => {
+                return self.list(items, &carryover_tags, &block.span, output)
+            }
+
+            BlockKind::Quote(items) => self.quote(items),
+
+            BlockKind::RangedTag(tag) => self.ranged_tag(tag),
+
+            BlockKind::InfirmTag(tag) => self.infirm_tag(tag),
+
+            BlockKind::Carryover(tags, target) => {
+                self.carryover_tags = carryover_tags;
+                self.carryover_tags.extend(tags);
+                self.block(target, output);
+                return;
+            }
+
+            BlockKind::HorizontalRule => Block::HorizontalRule,
+
+            BlockKind::Unsupported(raw) => Block::Plain(vec![Inline::Str(raw.clone())]),
+
+            BlockKind::Error(raw) => Block::Div(
+                self.attr(
+                    String::new(),
+                    vec![String::from("parse-error")],
+                    &block.span,
+                ),
+                vec![Block::Plain(vec![Inline::Str(raw.clone())])],
+            ),
+        };
+
+        output.push(block);
+    }
+
+    /// Lowers the content of a list or quote item.
+    ///
+    /// The detached modifier extension (e.g. a todo state) is put in front of the first
+    /// paragraph
+    fn nestable_content(&mut self, item: &'a Nestable) -> Vec<Block> {
+        let mut content = self.blocks(&item.content);
+
+        if let Some(extension) = &item.extension {
+            let mut prefix = self.extension(extension, &item.span);
+            if let Some(inlines) = self.first_paragraph(&mut content) {
+                prefix.append(inlines);
+                *inlines = prefix;
+            } else {
+                content.insert(0, Block::Plain(prefix));
+            }
+        }
+
+        content
+    }
+
+    /// The text of a leading paragraph, also when it's wrapped for its source position
+    fn first_paragraph<'b>(&self, content: &'b mut [Block]) -> Option<&'b mut Vec<Inline>> {
+        match content.first_mut()? {
+            Block::Para(inlines) => Some(inlines),
+            Block::Div((id, classes, _), blocks)
+                if self.options.source_positions && id.is_empty() && classes.is_empty() =>
+            {
+                match blocks.as_mut_slice() {
+                    [Block::Para(inlines)] => Some(inlines),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn lower(document: &norg::Document, options: &ParseOptions) -> Parsed {
+    let heading_shift = options.heading_offset
+        + match options.deep_headings {
+            DeepHeadings::Rebase => {
+                heading::shallowest_level(&document.content).map_or(0, |level| 1 - level)
+            }
+            DeepHeadings::Clamp | DeepHeadings::Div => 0,
+        };
+
+    let mut lowering = Lowering {
+        name: document.name.as_deref(),
+        metadata: &document.metadata,
+        options,
+        carryover_tags: vec![],
+        heading_shift,
+        targets: link::Targets::collect(
+            &document.content,
+            options.heading_ids,
+            options.heading_offset,
+        ),
+        links: LinkReport::default(),
+        diagnostics: vec![],
+    };
+    for duplicate in std::mem::take(&mut lowering.targets.duplicates) {
+        lowering.warn(
+            &duplicate.span,
+            format!(
+                "the {} {} is already defined at {}:{}",
+                duplicate.kind, duplicate.name, duplicate.first.line, duplicate.first.column
+            ),
+        );
+        lowering.links.duplicates.push(duplicate);
+    }
+
+    let blocks = lowering.blocks(&document.content);
+
+    Parsed {
+        ast: Pandoc {
+            meta: metadata(&document.metadata),
+            blocks,
+            pandoc_api_version: options.api_version.clone(),
+        },
+        diagnostics: lowering.diagnostics,
+        links: lowering.links,
+    }
+}
+
+/// Turns the `@document.meta` key value pairs into pandoc metadata strings
+fn metadata(metadata: &BTreeMap<String, String>) -> Map<String, MetaValue> {
+    let mut output = Map::default();
+    for (key, value) in metadata {
+        let key = match key.as_str() {
+            "authors" => String::from("author"),
+            key => key.to_owned(),
+        };
+        output.insert(key, MetaValue::MetaString(value.clone()));
+    }
+    output
+}
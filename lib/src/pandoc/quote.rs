@@ -0,0 +1,57 @@
+use pandoc_ast::Block;
+
+use crate::norg::Nestable;
+
+use super::Lowering;
+
+/// A single quote item with its lowered content
+struct Item {
+    nesting: usize,
+    content: Vec<Block>,
+}
+
+impl<'a> Lowering<'a> {
+    pub(super) fn quote(&mut self, items: &'a [Nestable]) -> Block {
+        let items = items
+            .iter()
+            .map(|item| Item {
+                nesting: item.level,
+                content: self.nestable_content(item),
+            })
+            .collect();
+
+        build_quote(items)
+    }
+}
+
+/// Builds the nested block quotes from the flat items.
+///
+/// Consecutive items on the same level end up in the same quote
+fn build_quote(items: Vec<Item>) -> Block {
+    // The content of every open quote, one per nesting level
+    let mut quotes: Vec<Vec<Block>> = vec![vec![]];
+
+    for mut item in items {
+        let nesting = item.nesting.max(1);
+        while quotes.len() > nesting {
+            close_quote(&mut quotes);
+        }
+        while quotes.len() < nesting {
+            quotes.push(vec![]);
+        }
+
+        quotes.last_mut().unwrap().append(&mut item.content);
+    }
+
+    while quotes.len() > 1 {
+        close_quote(&mut quotes);
+    }
+
+    Block::BlockQuote(quotes.pop().unwrap())
+}
+
+/// Closes the innermost open quote and adds it to the content of its parent
+fn close_quote(quotes: &mut Vec<Vec<Block>>) {
+    let quote = Block::BlockQuote(quotes.pop().unwrap());
+    quotes.last_mut().unwrap().push(quote);
+}
@@ -0,0 +1,314 @@
+use pandoc_ast::{Alignment, Attr, Block, Cell, ColWidth, Inline, Row};
+
+/// Reads the content of a `@table` as a github flavoured markdown table.
+///
+/// Returns `None` if it doesn't start with a header and a delimiter row of the same width
+pub(super) fn parse(content: &str) -> Option<Block> {
+    let mut lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let header = cells(lines.next()?);
+    let columns = cells(lines.next()?)
+        .iter()
+        .map(|cell| alignment(cell))
+        .collect::<Option<Vec<_>>>()?;
+    if header.len() != columns.len() {
+        return None;
+    }
+
+    let row = |cells: Vec<String>| -> Row {
+        let mut cells: Vec<Cell> = cells
+            .into_iter()
+            .zip(&columns)
+            .map(|(text, alignment)| {
+                let content = if text.is_empty() {
+                    vec![]
+                } else {
+                    vec![Block::Plain(inlines(&text))]
+                };
+                (
+                    (String::new(), vec![], vec![]),
+                    alignment.clone(),
+                    1,
+                    1,
+                    content,
+                )
+            })
+            .collect();
+        // Short rows are filled up with empty cells, the rest of long rows is left out
+        for alignment in &columns[cells.len()..] {
+            cells.push((
+                (String::new(), vec![], vec![]),
+                alignment.clone(),
+                1,
+                1,
+                vec![],
+            ));
+        }
+        ((String::new(), vec![], vec![]), cells)
+    };
+
+    let head = vec![row(header)];
+    let body = lines.map(|line| row(cells(line))).collect();
+    Some(Block::Table(
+        (String::new(), vec![], vec![]),
+        (None, vec![]),
+        columns
+            .iter()
+            .map(|alignment| (alignment.clone(), ColWidth::ColWidthDefault))
+            .collect(),
+        ((String::new(), vec![], vec![]), head),
+        vec![((String::new(), vec![], vec![]), 0, vec![], body)],
+        ((String::new(), vec![], vec![]), vec![]),
+    ))
+}
+
+/// Splits a table row at the pipes that aren't escaped, the outer pipes are optional
+fn cells(line: &str) -> Vec<String> {
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+
+    let mut cells = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('|') => cells.last_mut().unwrap().push('|'),
+                Some(next) => {
+                    cells.last_mut().unwrap().push('\\');
+                    cells.last_mut().unwrap().push(next);
+                }
+                None => cells.last_mut().unwrap().push('\\'),
+            },
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|cell| cell.trim().to_owned()).collect()
+}
+
+/// The alignment a cell of the delimiter row sets, `None` if it isn't one like `:---`
+fn alignment(cell: &str) -> Option<Alignment> {
+    let left = cell.starts_with(':');
+    let right = cell.ends_with(':') && cell.len() > 1;
+    let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+    if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+        return None;
+    }
+
+    Some(match (left, right) {
+        (true, true) => Alignment::AlignCenter,
+        (true, false) => Alignment::AlignLeft,
+        (false, true) => Alignment::AlignRight,
+        (false, false) => Alignment::AlignDefault,
+    })
+}
+
+/// Reads the inline markup of a cell like github does for the common cases, `**strong**`,
+/// `*emphasis*`, `~~strikeout~~`, `` `code` ``, `[links](url)` and backslash escapes. Everything
+/// else is text
+fn inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = vec![];
+    let mut word = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            push_word(&mut word, &mut inlines);
+            if !matches!(inlines.last(), None | Some(Inline::Space)) {
+                inlines.push(Inline::Space);
+            }
+            rest = rest.trim_start();
+            continue;
+        }
+
+        if let Some((inline, after)) = markup(rest, word.chars().last()) {
+            push_word(&mut word, &mut inlines);
+            inlines.push(inline);
+            rest = after;
+            continue;
+        }
+
+        let escaped = rest[c.len_utf8()..]
+            .chars()
+            .next()
+            .filter(|next| c == '\\' && next.is_ascii_punctuation());
+        match escaped {
+            Some(next) => {
+                word.push(next);
+                rest = &rest[1 + next.len_utf8()..];
+            }
+            None => {
+                word.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    push_word(&mut word, &mut inlines);
+
+    inlines
+}
+
+fn push_word(word: &mut String, inlines: &mut Vec<Inline>) {
+    if !word.is_empty() {
+        inlines.push(Inline::Str(std::mem::take(word)));
+    }
+}
+
+/// The markup at the start of the text together with the text after it, `previous` is the
+/// character in front of it within the same word
+fn markup(text: &str, previous: Option<char>) -> Option<(Inline, &str)> {
+    if text.starts_with('`') {
+        let fence = &text[..text.len() - text.trim_start_matches('`').len()];
+        let end = text[fence.len()..].find(fence)? + fence.len();
+        let code = text[fence.len()..end].trim().to_owned();
+        return Some((Inline::Code(no_attr(), code), &text[end + fence.len()..]));
+    }
+
+    if let Some(after) = text.strip_prefix('[') {
+        let close = after.find("](")?;
+        let end = after[close..].find(')')? + close;
+        let description = inlines(&after[..close]);
+        let url = after[close + 2..end].trim().to_owned();
+        return Some((
+            Inline::Link(no_attr(), description, (url, String::new())),
+            &after[end + 1..],
+        ));
+    }
+
+    for delimiter in ["**", "__", "~~", "*", "_"] {
+        let Some(after) = text.strip_prefix(delimiter) else {
+            continue;
+        };
+        // Underscores within words like snake_case don't emphasize anything
+        if delimiter.starts_with('_') && previous.map_or(false, char::is_alphanumeric) {
+            return None;
+        }
+        let Some(end) = after.find(delimiter) else {
+            continue;
+        };
+        let content = &after[..end];
+        if content.is_empty()
+            || content.starts_with(char::is_whitespace)
+            || content.ends_with(char::is_whitespace)
+        {
+            continue;
+        }
+        let content = inlines(content);
+        let inline = match delimiter {
+            "**" | "__" => Inline::Strong(content),
+            "~~" => Inline::Strikeout(content),
+            _ => Inline::Emph(content),
+        };
+        return Some((inline, &after[end + delimiter.len()..]));
+    }
+
+    None
+}
+
+fn no_attr() -> Attr {
+    (String::new(), vec![], vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_rows_at_unescaped_pipes() {
+        assert_eq!(cells("| a | b \\| c |"), ["a", "b | c"]);
+        assert_eq!(cells("a|b"), ["a", "b"]);
+        assert_eq!(cells("| a | |"), ["a", ""]);
+    }
+
+    #[test]
+    fn reads_alignments() {
+        assert_eq!(alignment(":---"), Some(Alignment::AlignLeft));
+        assert_eq!(alignment("---:"), Some(Alignment::AlignRight));
+        assert_eq!(alignment(":-:"), Some(Alignment::AlignCenter));
+        assert_eq!(alignment("---"), Some(Alignment::AlignDefault));
+        assert_eq!(alignment(":"), None);
+        assert_eq!(alignment("text"), None);
+    }
+
+    #[test]
+    fn needs_a_delimiter_row_as_wide_as_the_header() {
+        assert!(parse("| a | b |\n| --- | --- |\n| 1 | 2 |").is_some());
+        assert!(parse("| a | b |\n| --- |").is_none());
+        assert!(parse("just text").is_none());
+    }
+
+    fn text(text: &str) -> Inline {
+        Inline::Str(text.to_owned())
+    }
+
+    #[test]
+    fn reads_inline_markup_in_cells() {
+        assert_eq!(
+            inlines("**bold** and *it*"),
+            [
+                Inline::Strong(vec![text("bold")]),
+                Inline::Space,
+                text("and"),
+                Inline::Space,
+                Inline::Emph(vec![text("it")]),
+            ]
+        );
+        assert_eq!(
+            inlines("`a*b` ~~old~~"),
+            [
+                Inline::Code(no_attr(), String::from("a*b")),
+                Inline::Space,
+                Inline::Strikeout(vec![text("old")]),
+            ]
+        );
+        assert_eq!(
+            inlines("see [the site](https://example.org)."),
+            [
+                text("see"),
+                Inline::Space,
+                Inline::Link(
+                    no_attr(),
+                    vec![text("the"), Inline::Space, text("site")],
+                    (String::from("https://example.org"), String::new())
+                ),
+                text("."),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_text_without_markup() {
+        assert_eq!(inlines("snake_case_name"), [text("snake_case_name")]);
+        assert_eq!(
+            inlines("2 * 3"),
+            [
+                text("2"),
+                Inline::Space,
+                text("*"),
+                Inline::Space,
+                text("3")
+            ]
+        );
+        assert_eq!(inlines("\\*not\\*"), [text("*not*")]);
+        assert_eq!(
+            inlines("[no link]"),
+            [text("[no"), Inline::Space, text("link]")]
+        );
+    }
+
+    #[test]
+    fn cells_keep_their_markup() {
+        let Some(Block::Table(_, _, _, _, bodies, _)) = parse("| a |\n| - |\n| **b** |") else {
+            panic!("expected a table");
+        };
+        let (_, cells) = &bodies[0].3[0];
+        assert_eq!(
+            cells[0].4,
+            [Block::Plain(vec![Inline::Strong(vec![text("b")])])]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use pandoc_ast::{Block, Inline};
+
+use crate::norg::Tag;
+
+use super::{table, Lowering};
+
+impl<'a> Lowering<'a> {
+    pub(super) fn ranged_tag(&mut self, tag: &Tag) -> Block {
+        if tag.name == "code" {
+            return Block::CodeBlock(
+                self.attr(String::new(), tag.parameters.clone(), &tag.span),
+                tag.content.clone(),
+            );
+        }
+
+        if tag.name == "table" {
+            match table::parse(&tag.content) {
+                Some(table) => {
+                    return Block::Div(
+                        self.attr(String::new(), tag.parameters.clone(), &tag.span),
+                        vec![table],
+                    );
+                }
+                None => self.warn(&tag.span, "the content is not a table, keeping it as code"),
+            }
+        }
+
+        let mut classes = vec![tag.name.clone()];
+        classes.extend(tag.parameters.iter().cloned());
+
+        Block::CodeBlock(
+            self.attr(String::new(), classes, &tag.span),
+            tag.content.clone(),
+        )
+    }
+
+    pub(super) fn infirm_tag(&mut self, tag: &Tag) -> Block {
+        if tag.name == "image" {
+            Block::Plain(vec![Inline::Image(
+                (String::new(), vec![], vec![]),
+                vec![],
+                (tag.parameters.join(" "), String::default()),
+            )])
+        } else {
+            self.warn(&tag.span, "not supported yet, keeping the raw text");
+            let mut raw = format!(".{}", tag.name);
+            for parameter in &tag.parameters {
+                raw.push(' ');
+                raw.push_str(parameter);
+            }
+            Block::Plain(vec![Inline::Str(raw)])
+        }
+    }
+}
@@ -0,0 +1,541 @@
+//! Checks which parts of a document survive being converted to pandoc and written back as norg
+
+use std::fmt::Display;
+
+use pandoc_ast::{Attr, Block, Inline, MetaValue, Pandoc};
+
+use crate::{
+    visit::{self, VisitMut},
+    writer::{block, inline},
+    Diagnostic, Span,
+};
+
+/// The result of converting a document to pandoc, writing it back as norg and converting that
+/// again
+pub struct RoundTrip {
+    /// The norg source that was written from the pandoc AST
+    pub source: String,
+    /// Everything that's different in the pandoc AST of the written source, in document order
+    pub differences: Vec<Difference>,
+    /// The diagnostics of converting the original document and of writing it back
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The element is missing after the round trip
+    Lost,
+    /// The element only exists after the round trip
+    Added,
+    /// The element turned into something else
+    Changed,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Lost => write!(f, "lost"),
+            Change::Added => write!(f, "added"),
+            Change::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// A single element of the pandoc AST that didn't survive the round trip
+#[derive(Debug, Clone)]
+pub struct Difference {
+    pub change: Change,
+    /// Where the element or the block around it is in the original source, if known
+    pub span: Option<Span>,
+    /// A short description of the original element
+    pub original: Option<String>,
+    /// A short description of the element after the round trip
+    pub round_tripped: Option<String>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.line, span.column)?;
+        }
+        write!(f, "{}", self.change)?;
+        match (&self.original, &self.round_tripped) {
+            (Some(original), Some(round_tripped)) => write!(f, ": {original} -> {round_tripped}"),
+            (Some(element), None) | (None, Some(element)) => write!(f, ": {element}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Compares the pandoc AST of the original document, lowered with source positions, with the
+/// one of the written norg source
+pub(crate) fn compare(source: &str, original: &Pandoc, round_tripped: &Pandoc) -> Vec<Difference> {
+    let mut comparison = Comparison {
+        source,
+        differences: vec![],
+    };
+    comparison.metadata(original, round_tripped);
+    comparison.blocks(&original.blocks, &round_tripped.blocks, None);
+    comparison.differences
+}
+
+struct Comparison<'a> {
+    /// The original source, to turn the `data-pos` attributes back into spans
+    source: &'a str,
+    differences: Vec<Difference>,
+}
+
+impl<'a> Comparison<'a> {
+    fn difference(
+        &mut self,
+        change: Change,
+        span: Option<&Span>,
+        original: Option<String>,
+        round_tripped: Option<String>,
+    ) {
+        self.differences.push(Difference {
+            change,
+            span: span.cloned(),
+            original,
+            round_tripped,
+        });
+    }
+
+    fn metadata(&mut self, original: &Pandoc, round_tripped: &Pandoc) {
+        for (key, value) in &original.meta {
+            match round_tripped.meta.get(key) {
+                Some(round_tripped) if round_tripped == value => {}
+                Some(round_tripped) => self.difference(
+                    Change::Changed,
+                    None,
+                    Some(format!("metadata {key}: {}", meta_text(value))),
+                    Some(format!("metadata {key}: {}", meta_text(round_tripped))),
+                ),
+                None => self.difference(Change::Lost, None, Some(format!("metadata {key}")), None),
+            }
+        }
+        for key in round_tripped.meta.keys() {
+            if !original.meta.contains_key(key) {
+                self.difference(Change::Added, None, None, Some(format!("metadata {key}")));
+            }
+        }
+    }
+
+    /// Compares two lists of blocks, the original ones carry source positions
+    fn blocks(&mut self, original: &[Block], round_tripped: &[Block], span: Option<&Span>) {
+        let mut original_blocks = vec![];
+        self.flatten(original, span, &mut original_blocks);
+        let mut round_tripped_blocks = vec![];
+        self.flatten(round_tripped, None, &mut round_tripped_blocks);
+
+        let edits = diff(
+            &original_blocks
+                .iter()
+                .map(|(block, _)| normalized_block(block))
+                .collect::<Vec<_>>(),
+            &round_tripped_blocks
+                .iter()
+                .map(|(block, _)| normalized_block(block))
+                .collect::<Vec<_>>(),
+        );
+
+        for edit in edits {
+            match edit {
+                Edit::Same => {}
+                Edit::Lost(index) => {
+                    let (block, span) = &original_blocks[index];
+                    self.difference(
+                        Change::Lost,
+                        span.as_ref(),
+                        Some(describe_block(block)),
+                        None,
+                    )
+                }
+                Edit::Added(index) => {
+                    let (block, _) = &round_tripped_blocks[index];
+                    self.difference(Change::Added, span, None, Some(describe_block(block)))
+                }
+                Edit::Changed(original_index, round_tripped_index) => {
+                    let (original, span) = &original_blocks[original_index];
+                    let (round_tripped, _) = &round_tripped_blocks[round_tripped_index];
+                    self.block(original, round_tripped, span.as_ref());
+                }
+            }
+        }
+    }
+
+    /// Compares two blocks that differ, looking into them if they are of the same kind
+    fn block(&mut self, original: &Block, round_tripped: &Block, span: Option<&Span>) {
+        let differences = self.differences.len();
+        match (original, round_tripped) {
+            (
+                Block::Para(original_inlines) | Block::Plain(original_inlines),
+                Block::Para(round_tripped_inlines) | Block::Plain(round_tripped_inlines),
+            ) => self.inlines(original_inlines, round_tripped_inlines, span),
+            (
+                Block::Header(original_level, _, original_inlines),
+                Block::Header(round_tripped_level, _, round_tripped_inlines),
+            ) if original_level == round_tripped_level => {
+                self.inlines(original_inlines, round_tripped_inlines, span)
+            }
+            (Block::BlockQuote(original_blocks), Block::BlockQuote(round_tripped_blocks))
+            | (Block::Div(_, original_blocks), Block::Div(_, round_tripped_blocks)) => {
+                self.blocks(original_blocks, round_tripped_blocks, span)
+            }
+            (Block::BulletList(original_items), Block::BulletList(round_tripped_items))
+            | (Block::OrderedList(_, original_items), Block::OrderedList(_, round_tripped_items)) => {
+                for (original_item, round_tripped_item) in
+                    original_items.iter().zip(round_tripped_items)
+                {
+                    self.blocks(original_item, round_tripped_item, span);
+                }
+            }
+            _ => {}
+        }
+
+        // Nothing inside differs, so the block itself does, e.g. its attributes or list style
+        if self.differences.len() == differences {
+            self.difference(
+                Change::Changed,
+                span,
+                Some(describe_block(original)),
+                Some(describe_block(round_tripped)),
+            );
+        }
+    }
+
+    fn inlines(&mut self, original: &[Inline], round_tripped: &[Inline], span: Option<&Span>) {
+        let edits = diff(
+            &original.iter().map(normalized_inline).collect::<Vec<_>>(),
+            &round_tripped
+                .iter()
+                .map(normalized_inline)
+                .collect::<Vec<_>>(),
+        );
+
+        for edit in edits {
+            match edit {
+                Edit::Same => {}
+                Edit::Lost(index) => {
+                    let inline_span = self.inline_span(&original[index]);
+                    self.difference(
+                        Change::Lost,
+                        inline_span.as_ref().or(span),
+                        Some(describe_inline(&original[index])),
+                        None,
+                    )
+                }
+                Edit::Added(index) => self.difference(
+                    Change::Added,
+                    span,
+                    None,
+                    Some(describe_inline(&round_tripped[index])),
+                ),
+                Edit::Changed(original_index, round_tripped_index) => {
+                    let inline_span = self.inline_span(&original[original_index]);
+                    self.difference(
+                        Change::Changed,
+                        inline_span.as_ref().or(span),
+                        Some(describe_inline(&original[original_index])),
+                        Some(describe_inline(&round_tripped[round_tripped_index])),
+                    )
+                }
+            }
+        }
+    }
+
+    /// Unwraps the `Div`s that only hold a source position or group a section, together with
+    /// the position of every block
+    fn flatten<'b>(
+        &self,
+        blocks: &'b [Block],
+        span: Option<&Span>,
+        output: &mut Vec<(&'b Block, Option<Span>)>,
+    ) {
+        for block in blocks {
+            let block_span = match block {
+                Block::Header(_, attr, _) | Block::CodeBlock(attr, _) | Block::Div(attr, _) => {
+                    self.span(attr)
+                }
+                _ => None,
+            }
+            .or_else(|| span.cloned());
+
+            match block {
+                Block::Div((id, classes, attributes), content)
+                    if id.is_empty()
+                        && classes.is_empty()
+                        && attributes.iter().all(|(key, _)| key == "data-pos") =>
+                {
+                    self.flatten(content, block_span.as_ref(), output)
+                }
+                block => output.push((block, block_span)),
+            }
+        }
+    }
+
+    fn inline_span(&self, inline: &Inline) -> Option<Span> {
+        match inline {
+            Inline::Span(attr, _) => self.span(attr),
+            _ => None,
+        }
+    }
+
+    /// Turns a `data-pos="line:column-line:column"` attribute back into a span
+    fn span(&self, (_, _, attributes): &Attr) -> Option<Span> {
+        let (_, position) = attributes.iter().find(|(key, _)| key == "data-pos")?;
+        let (start, end) = position.split_once('-')?;
+        let (line, column) = line_column(start)?;
+        let (end_line, end_column) = line_column(end)?;
+
+        Some(Span {
+            byte_range: self.offset(line, column)?..self.offset(end_line, end_column)?,
+            line,
+            column,
+            end_line,
+            end_column,
+        })
+    }
+
+    /// The byte offset of a 1-based line and byte column
+    fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let line_start = if line == 1 {
+            0
+        } else {
+            self.source.match_indices('\n').nth(line - 2)?.0 + 1
+        };
+        Some(line_start + column - 1)
+    }
+}
+
+fn line_column(position: &str) -> Option<(usize, usize)> {
+    let (line, column) = position.split_once(':')?;
+    Some((line.parse().ok()?, column.parse().ok()?))
+}
+
+/// Removes the source positions and the `Div`s without attributes, which only group sections
+struct Normalize;
+
+impl VisitMut for Normalize {
+    fn visit_blocks(&mut self, blocks: &mut Vec<Block>) {
+        visit::walk_blocks(self, blocks);
+        let mut flattened = Vec::with_capacity(blocks.len());
+        for block in blocks.drain(..) {
+            match block {
+                Block::Div((id, classes, attributes), mut content)
+                    if id.is_empty() && classes.is_empty() && attributes.is_empty() =>
+                {
+                    flattened.append(&mut content)
+                }
+                block => flattened.push(block),
+            }
+        }
+        *blocks = flattened;
+    }
+
+    fn visit_attr(&mut self, (_, _, attributes): &mut Attr) {
+        attributes.retain(|(key, _)| key != "data-pos");
+    }
+}
+
+fn normalized_block(block: &Block) -> Block {
+    let mut block = block.clone();
+    Normalize.visit_block(&mut block);
+    block
+}
+
+fn normalized_inline(inline: &Inline) -> Inline {
+    let mut inline = inline.clone();
+    Normalize.visit_inline(&mut inline);
+    inline
+}
+
+#[derive(Debug, PartialEq)]
+enum Edit {
+    Same,
+    Lost(usize),
+    Added(usize),
+    Changed(usize, usize),
+}
+
+/// Finds the elements that were lost or added with a longest common subsequence. Lost and added
+/// elements between the same common ones are paired up as changed
+fn diff<T: PartialEq>(original: &[T], round_tripped: &[T]) -> Vec<Edit> {
+    let (rows, columns) = (original.len(), round_tripped.len());
+    // The length of the common subsequence of the remaining elements
+    let mut common = vec![vec![0_usize; columns + 1]; rows + 1];
+    for row in (0..rows).rev() {
+        for column in (0..columns).rev() {
+            common[row][column] = if original[row] == round_tripped[column] {
+                common[row + 1][column + 1] + 1
+            } else {
+                common[row + 1][column].max(common[row][column + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![];
+    let (mut lost, mut added) = (vec![], vec![]);
+    let (mut row, mut column) = (0, 0);
+    while row < rows || column < columns {
+        if row < rows && column < columns && original[row] == round_tripped[column] {
+            pair_up(&mut lost, &mut added, &mut edits);
+            edits.push(Edit::Same);
+            row += 1;
+            column += 1;
+        } else if column == columns
+            || (row < rows && common[row + 1][column] >= common[row][column + 1])
+        {
+            lost.push(row);
+            row += 1;
+        } else {
+            added.push(column);
+            column += 1;
+        }
+    }
+    pair_up(&mut lost, &mut added, &mut edits);
+
+    edits
+}
+
+fn pair_up(lost: &mut Vec<usize>, added: &mut Vec<usize>, edits: &mut Vec<Edit>) {
+    let paired = lost.len().min(added.len());
+    edits.extend(
+        lost.iter()
+            .zip(added.iter())
+            .map(|(&lost, &added)| Edit::Changed(lost, added)),
+    );
+    edits.extend(lost[paired..].iter().map(|&index| Edit::Lost(index)));
+    edits.extend(added[paired..].iter().map(|&index| Edit::Added(index)));
+    lost.clear();
+    added.clear();
+}
+
+/// The longest excerpt of text in a description
+const EXCERPT_LENGTH: usize = 40;
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{:?}", format!("{}...", &text[..end])),
+        None => format!("{text:?}"),
+    }
+}
+
+fn describe_block(block: &Block) -> String {
+    let (name, text) = match block {
+        Block::Plain(inlines) => ("Plain", inline::plain_text(inlines)),
+        Block::Para(inlines) => ("Para", inline::plain_text(inlines)),
+        Block::LineBlock(_) => ("LineBlock", block::plain_text(std::slice::from_ref(block))),
+        Block::CodeBlock(_, code) => ("CodeBlock", code.clone()),
+        Block::RawBlock(_, raw) => ("RawBlock", raw.clone()),
+        Block::BlockQuote(_) => ("BlockQuote", block::plain_text(std::slice::from_ref(block))),
+        Block::OrderedList(_, _) => (
+            "OrderedList",
+            block::plain_text(std::slice::from_ref(block)),
+        ),
+        Block::BulletList(_) => ("BulletList", block::plain_text(std::slice::from_ref(block))),
+        Block::DefinitionList(_) => (
+            "DefinitionList",
+            block::plain_text(std::slice::from_ref(block)),
+        ),
+        Block::Header(level, _, inlines) => {
+            return format!("Header {level} {}", excerpt(&inline::plain_text(inlines)))
+        }
+        Block::HorizontalRule => return String::from("HorizontalRule"),
+        Block::Table(..) => return String::from("Table"),
+        Block::Figure(_, _, content) => ("Figure", block::plain_text(content)),
+        Block::Div(_, content) => ("Div", block::plain_text(content)),
+        Block::Null => return String::from("Null"),
+    };
+    format!("{name} {}", excerpt(&text))
+}
+
+fn describe_inline(inline: &Inline) -> String {
+    let name = match inline {
+        Inline::Str(_) => "Str",
+        Inline::Emph(_) => "Emph",
+        Inline::Underline(_) => "Underline",
+        Inline::Strong(_) => "Strong",
+        Inline::Strikeout(_) => "Strikeout",
+        Inline::Superscript(_) => "Superscript",
+        Inline::Subscript(_) => "Subscript",
+        Inline::SmallCaps(_) => "SmallCaps",
+        Inline::Quoted(_, _) => "Quoted",
+        Inline::Cite(_, _) => "Cite",
+        Inline::Code(_, _) => "Code",
+        Inline::Space => return String::from("Space"),
+        Inline::SoftBreak => return String::from("SoftBreak"),
+        Inline::LineBreak => return String::from("LineBreak"),
+        Inline::Math(_, _) => "Math",
+        Inline::RawInline(_, _) => "RawInline",
+        Inline::Link(_, _, (url, _)) => {
+            return format!(
+                "Link {} to {url:?}",
+                excerpt(&inline::plain_text(std::slice::from_ref(inline)))
+            )
+        }
+        Inline::Image(_, _, (source, _)) => return format!("Image {source:?}"),
+        Inline::Note(content) => return format!("Note {}", excerpt(&block::plain_text(content))),
+        Inline::Span(_, _) => "Span",
+    };
+    format!(
+        "{name} {}",
+        excerpt(&inline::plain_text(std::slice::from_ref(inline)))
+    )
+}
+
+fn meta_text(value: &MetaValue) -> String {
+    match value {
+        MetaValue::MetaString(text) => excerpt(text),
+        MetaValue::MetaInlines(inlines) => excerpt(&inline::plain_text(inlines)),
+        MetaValue::MetaBlocks(blocks) => excerpt(&block::plain_text(blocks)),
+        MetaValue::MetaBool(value) => value.to_string(),
+        MetaValue::MetaList(_) | MetaValue::MetaMap(_) => String::from("..."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_elements_have_no_changes() {
+        assert_eq!(diff(&[1, 2], &[1, 2]), [Edit::Same, Edit::Same]);
+        assert_eq!(diff::<i32>(&[], &[]), []);
+    }
+
+    #[test]
+    fn finds_lost_and_added_elements() {
+        assert_eq!(
+            diff(&[1, 2, 3], &[1, 3]),
+            [Edit::Same, Edit::Lost(1), Edit::Same]
+        );
+        assert_eq!(
+            diff(&[1, 3], &[1, 2, 3]),
+            [Edit::Same, Edit::Added(1), Edit::Same]
+        );
+        assert_eq!(diff(&[], &[1]), [Edit::Added(0)]);
+    }
+
+    #[test]
+    fn pairs_up_lost_and_added_elements_as_changed() {
+        assert_eq!(
+            diff(&[1, 2, 3], &[1, 4, 3]),
+            [Edit::Same, Edit::Changed(1, 1), Edit::Same]
+        );
+        assert_eq!(
+            diff(&[1, 2, 3], &[1, 4, 5, 3]),
+            [Edit::Same, Edit::Changed(1, 1), Edit::Added(2), Edit::Same]
+        );
+        assert_eq!(
+            diff(&[1, 2, 3, 4], &[5, 2, 6]),
+            [
+                Edit::Changed(0, 0),
+                Edit::Same,
+                Edit::Changed(2, 2),
+                Edit::Lost(3)
+            ]
+        );
+    }
+}
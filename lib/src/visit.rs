@@ -0,0 +1,293 @@
+//! A mutable walk over the whole pandoc AST for passes that run after the lowering, like the
+//! normalization of the round trip check. Link resolution doesn't need it, it happens on the
+//! norg representation with `norg::Visit` before the lowering.
+//!
+//! Every method walks into the children by default, so a pass only overrides the nodes it
+//! cares about and calls the matching `walk_` function to keep going. The `walk_` functions
+//! match on every variant, so the AST can contain anything pandoc knows about
+
+use pandoc_ast::{Attr, Block, Caption, Inline, MetaValue, Pandoc, Row};
+
+pub trait VisitMut {
+    fn visit_pandoc(&mut self, pandoc: &mut Pandoc) {
+        walk_pandoc(self, pandoc)
+    }
+
+    fn visit_meta_value(&mut self, value: &mut MetaValue) {
+        walk_meta_value(self, value)
+    }
+
+    /// Visits a list of blocks, override it to add or remove blocks
+    fn visit_blocks(&mut self, blocks: &mut Vec<Block>) {
+        walk_blocks(self, blocks)
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        walk_block(self, block)
+    }
+
+    /// Visits a list of inlines, override it to add or remove inlines
+    fn visit_inlines(&mut self, inlines: &mut Vec<Inline>) {
+        walk_inlines(self, inlines)
+    }
+
+    fn visit_inline(&mut self, inline: &mut Inline) {
+        walk_inline(self, inline)
+    }
+
+    /// Visits the attributes of any block or inline that has them
+    fn visit_attr(&mut self, _attr: &mut Attr) {}
+}
+
+pub fn walk_pandoc<V: VisitMut + ?Sized>(visitor: &mut V, pandoc: &mut Pandoc) {
+    for value in pandoc.meta.values_mut() {
+        visitor.visit_meta_value(value);
+    }
+    visitor.visit_blocks(&mut pandoc.blocks);
+}
+
+pub fn walk_meta_value<V: VisitMut + ?Sized>(visitor: &mut V, value: &mut MetaValue) {
+    match value {
+        MetaValue::MetaMap(map) => {
+            for value in map.values_mut() {
+                visitor.visit_meta_value(value);
+            }
+        }
+        MetaValue::MetaList(values) => {
+            for value in values {
+                visitor.visit_meta_value(value);
+            }
+        }
+        MetaValue::MetaInlines(inlines) => visitor.visit_inlines(inlines),
+        MetaValue::MetaBlocks(blocks) => visitor.visit_blocks(blocks),
+        MetaValue::MetaBool(_) | MetaValue::MetaString(_) => {}
+    }
+}
+
+pub fn walk_blocks<V: VisitMut + ?Sized>(visitor: &mut V, blocks: &mut Vec<Block>) {
+    for block in blocks {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_inlines<V: VisitMut + ?Sized>(visitor: &mut V, inlines: &mut Vec<Inline>) {
+    for inline in inlines {
+        visitor.visit_inline(inline);
+    }
+}
+
+pub fn walk_block<V: VisitMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    match block {
+        Block::Plain(inlines) | Block::Para(inlines) => visitor.visit_inlines(inlines),
+        Block::LineBlock(lines) => {
+            for line in lines {
+                visitor.visit_inlines(line);
+            }
+        }
+        Block::CodeBlock(attr, _) => visitor.visit_attr(attr),
+        Block::BlockQuote(blocks) => visitor.visit_blocks(blocks),
+        Block::OrderedList(_, items) | Block::BulletList(items) => {
+            for item in items {
+                visitor.visit_blocks(item);
+            }
+        }
+        Block::DefinitionList(items) => {
+            for (term, definitions) in items {
+                visitor.visit_inlines(term);
+                for definition in definitions {
+                    visitor.visit_blocks(definition);
+                }
+            }
+        }
+        Block::Header(_, attr, inlines) => {
+            visitor.visit_attr(attr);
+            visitor.visit_inlines(inlines);
+        }
+        Block::Table(attr, caption, _, head, bodies, foot) => {
+            visitor.visit_attr(attr);
+            walk_caption(visitor, caption);
+            visitor.visit_attr(&mut head.0);
+            walk_rows(visitor, &mut head.1);
+            for body in bodies {
+                visitor.visit_attr(&mut body.0);
+                walk_rows(visitor, &mut body.2);
+                walk_rows(visitor, &mut body.3);
+            }
+            visitor.visit_attr(&mut foot.0);
+            walk_rows(visitor, &mut foot.1);
+        }
+        Block::Figure(attr, caption, blocks) => {
+            visitor.visit_attr(attr);
+            walk_caption(visitor, caption);
+            visitor.visit_blocks(blocks);
+        }
+        Block::Div(attr, blocks) => {
+            visitor.visit_attr(attr);
+            visitor.visit_blocks(blocks);
+        }
+        Block::RawBlock(_, _) | Block::HorizontalRule | Block::Null => {}
+    }
+}
+
+pub fn walk_inline<V: VisitMut + ?Sized>(visitor: &mut V, inline: &mut Inline) {
+    match inline {
+        Inline::Emph(inlines)
+        | Inline::Underline(inlines)
+        | Inline::Strong(inlines)
+        | Inline::Strikeout(inlines)
+        | Inline::Superscript(inlines)
+        | Inline::Subscript(inlines)
+        | Inline::SmallCaps(inlines)
+        | Inline::Quoted(_, inlines) => visitor.visit_inlines(inlines),
+        Inline::Cite(citations, inlines) => {
+            for citation in citations {
+                visitor.visit_inlines(&mut citation.citationPrefix);
+                visitor.visit_inlines(&mut citation.citationSuffix);
+            }
+            visitor.visit_inlines(inlines);
+        }
+        Inline::Code(attr, _) => visitor.visit_attr(attr),
+        Inline::Link(attr, inlines, _)
+        | Inline::Image(attr, inlines, _)
+        | Inline::Span(attr, inlines) => {
+            visitor.visit_attr(attr);
+            visitor.visit_inlines(inlines);
+        }
+        Inline::Note(blocks) => visitor.visit_blocks(blocks),
+        Inline::Str(_)
+        | Inline::Space
+        | Inline::SoftBreak
+        | Inline::LineBreak
+        | Inline::Math(_, _)
+        | Inline::RawInline(_, _) => {}
+    }
+}
+
+fn walk_caption<V: VisitMut + ?Sized>(visitor: &mut V, (short, long): &mut Caption) {
+    if let Some(short) = short {
+        visitor.visit_inlines(short);
+    }
+    visitor.visit_blocks(long);
+}
+
+fn walk_rows<V: VisitMut + ?Sized>(visitor: &mut V, rows: &mut [Row]) {
+    for (attr, cells) in rows {
+        visitor.visit_attr(attr);
+        for cell in cells {
+            visitor.visit_attr(&mut cell.0);
+            visitor.visit_blocks(&mut cell.4);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pandoc_ast::{Citation, CitationMode, Format, MathType, QuoteType};
+
+    use super::*;
+
+    /// Uppercases every `Str` and counts the leaves that have nothing to walk into
+    #[derive(Default)]
+    struct Uppercase {
+        leaves: Vec<&'static str>,
+    }
+
+    impl VisitMut for Uppercase {
+        fn visit_inline(&mut self, inline: &mut Inline) {
+            match inline {
+                Inline::Str(text) => *text = text.to_uppercase(),
+                Inline::Math(_, _) => self.leaves.push("Math"),
+                Inline::RawInline(_, _) => self.leaves.push("RawInline"),
+                Inline::LineBreak => self.leaves.push("LineBreak"),
+                _ => walk_inline(self, inline),
+            }
+        }
+    }
+
+    fn text(text: &str) -> Vec<Inline> {
+        vec![Inline::Str(text.to_owned())]
+    }
+
+    fn visited(mut block: Block) -> (Block, Vec<&'static str>) {
+        let mut visitor = Uppercase::default();
+        visitor.visit_block(&mut block);
+        (block, visitor.leaves)
+    }
+
+    #[test]
+    fn reaches_the_leaf_inlines() {
+        let (_, leaves) = visited(Block::Para(vec![
+            Inline::Math(MathType::InlineMath, String::from("x")),
+            Inline::RawInline(Format(String::from("html")), String::from("<br>")),
+            Inline::LineBreak,
+        ]));
+        assert_eq!(leaves, ["Math", "RawInline", "LineBreak"]);
+    }
+
+    #[test]
+    fn walks_into_notes_quotes_and_citations() {
+        let citation = Citation {
+            citationId: String::from("key"),
+            citationPrefix: text("see"),
+            citationSuffix: text("p. 1"),
+            citationMode: CitationMode::NormalCitation,
+            citationNoteNum: 0,
+            citationHash: 0,
+        };
+        let (block, _) = visited(Block::Para(vec![
+            Inline::Note(vec![Block::Para(text("note"))]),
+            Inline::Quoted(QuoteType::DoubleQuote, text("quote")),
+            Inline::Cite(vec![citation.clone()], text("cite")),
+        ]));
+        assert_eq!(
+            block,
+            Block::Para(vec![
+                Inline::Note(vec![Block::Para(text("NOTE"))]),
+                Inline::Quoted(QuoteType::DoubleQuote, text("QUOTE")),
+                Inline::Cite(
+                    vec![Citation {
+                        citationPrefix: text("SEE"),
+                        citationSuffix: text("P. 1"),
+                        ..citation
+                    }],
+                    text("CITE")
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn walks_into_line_blocks_definition_lists_and_figures() {
+        let no_attr = || (String::new(), vec![], vec![]);
+        let (block, _) = visited(Block::Div(
+            no_attr(),
+            vec![
+                Block::LineBlock(vec![text("one"), text("two")]),
+                Block::DefinitionList(vec![(text("term"), vec![vec![Block::Plain(text("def"))]])]),
+                Block::Figure(
+                    no_attr(),
+                    (Some(text("short")), vec![Block::Plain(text("caption"))]),
+                    vec![Block::Plain(text("figure"))],
+                ),
+            ],
+        ));
+        assert_eq!(
+            block,
+            Block::Div(
+                no_attr(),
+                vec![
+                    Block::LineBlock(vec![text("ONE"), text("TWO")]),
+                    Block::DefinitionList(vec![(
+                        text("TERM"),
+                        vec![vec![Block::Plain(text("DEF"))]]
+                    )]),
+                    Block::Figure(
+                        no_attr(),
+                        (Some(text("SHORT")), vec![Block::Plain(text("CAPTION"))]),
+                        vec![Block::Plain(text("FIGURE"))],
+                    ),
+                ],
+            )
+        );
+    }
+}
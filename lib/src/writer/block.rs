@@ -0,0 +1,416 @@
+use pandoc_ast::{
+    Alignment, Block, Caption, ColSpec, Format, Inline, ListAttributes, ListNumberDelim,
+    ListNumberStyle, Row, TableBody, TableHead,
+};
+
+use super::{inline, Writer};
+
+impl Writer {
+    /// Writes blocks separated by empty lines, each followed by the footnotes it references
+    pub(super) fn blocks(&mut self, blocks: &[Block], output: &mut String) {
+        for block in blocks {
+            separate(output);
+            self.block(block, output);
+            self.footnotes(output);
+        }
+    }
+
+    fn block(&mut self, block: &Block, output: &mut String) {
+        match block {
+            Block::Plain(inlines) | Block::Para(inlines) => match inlines.as_slice() {
+                [Inline::Image(_, _, (source, _))] => {
+                    output.push_str(&format!(".image {source}\n"))
+                }
+                _ => self.paragraph(inlines, output),
+            },
+
+            Block::LineBlock(lines) => {
+                for line in lines {
+                    self.inlines(line, output);
+                    output.push('\n');
+                }
+            }
+
+            Block::CodeBlock((_, classes, _), code) => {
+                output.push_str("@code");
+                if let Some(language) = classes.first() {
+                    output.push(' ');
+                    output.push_str(language);
+                }
+                output.push('\n');
+                if verbatim(code, output) {
+                    self.warn(
+                        "the code has a line that would end it, escaping it with a backslash",
+                    );
+                }
+                output.push_str("@end\n");
+            }
+
+            Block::RawBlock(Format(format), raw) if format == "norg" => {
+                output.push_str(raw);
+                if !raw.ends_with('\n') {
+                    output.push('\n');
+                }
+            }
+
+            Block::RawBlock(Format(format), _) => {
+                self.warn(format!("can't write raw {format}, leaving it out"))
+            }
+
+            Block::BlockQuote(blocks) => self.quote(blocks, 1, output),
+
+            Block::OrderedList(_, items) => {
+                output.push_str(&number_tags(block));
+                self.list(items, '~', 1, output);
+            }
+
+            Block::BulletList(items) => {
+                output.push_str(&number_tags(block));
+                self.list(items, '-', 1, output);
+            }
+
+            Block::DefinitionList(items) => {
+                for (term, definitions) in items {
+                    separate(output);
+                    output.push_str("$ ");
+                    self.inlines(term, output);
+                    output.push('\n');
+                    for definition in definitions {
+                        output.push_str(&plain_text(definition));
+                        output.push('\n');
+                    }
+                }
+            }
+
+            Block::Header(level, _, title) => {
+                output.push_str(&"*".repeat((*level).max(1) as usize));
+                output.push(' ');
+                self.inlines(title, output);
+                output.push('\n');
+            }
+
+            Block::HorizontalRule => output.push_str("___\n"),
+
+            Block::Table(_, caption, columns, head, bodies, foot) => {
+                output.push_str("@table\n");
+                output.push_str(&table(columns, head, bodies, &foot.1));
+                output.push_str("@end\n");
+                self.caption(caption, output);
+            }
+
+            Block::Figure(_, caption, blocks) => {
+                self.blocks(blocks, output);
+                self.caption(caption, output);
+            }
+
+            Block::Div(_, blocks) => self.blocks(blocks, output),
+
+            Block::Null => {}
+        }
+    }
+
+    fn paragraph(&mut self, inlines: &[Inline], output: &mut String) {
+        self.inlines(inlines, output);
+        output.push('\n');
+    }
+
+    fn caption(&mut self, (_, caption): &Caption, output: &mut String) {
+        self.blocks(caption, output);
+    }
+
+    /// Writes the items of a list, nested lists get a longer prefix
+    fn list(&mut self, items: &[Vec<Block>], marker: char, level: usize, output: &mut String) {
+        let prefix = marker.to_string().repeat(level);
+        for item in items {
+            output.push_str(&prefix);
+            self.item(item, output, |writer, block, output| {
+                match block {
+                    Block::BulletList(items) => writer.list(items, '-', level + 1, output),
+                    Block::OrderedList(_, items) => writer.list(items, '~', level + 1, output),
+                    _ => return false,
+                }
+                true
+            });
+        }
+    }
+
+    /// Writes the content of a quote, nested quotes get a longer prefix.
+    ///
+    /// Every paragraph becomes an item of its own, so they stay apart
+    fn quote(&mut self, blocks: &[Block], level: usize, output: &mut String) {
+        let prefix = ">".repeat(level);
+        let mut rest = blocks;
+        while !rest.is_empty() {
+            let end = rest[1..]
+                .iter()
+                .position(is_text)
+                .map_or(rest.len(), |index| index + 1);
+            output.push_str(&prefix);
+            self.item(&rest[..end], output, |writer, block, output| {
+                match block {
+                    Block::BlockQuote(blocks) => writer.quote(blocks, level + 1, output),
+                    _ => return false,
+                }
+                true
+            });
+            rest = &rest[end..];
+        }
+    }
+
+    /// Writes the blocks of a list item or quote right after its prefix.
+    ///
+    /// Text goes on the line of the prefix and the other blocks follow without an empty line, so
+    /// they stay in the item. `nested` writes the blocks that nest with a longer prefix and
+    /// returns whether the block was one of them
+    fn item(
+        &mut self,
+        blocks: &[Block],
+        output: &mut String,
+        mut nested: impl FnMut(&mut Self, &Block, &mut String) -> bool,
+    ) {
+        if blocks.first().map_or(false, is_text) {
+            output.push(' ');
+        } else {
+            output.push('\n');
+        }
+
+        for (index, block) in blocks.iter().enumerate() {
+            // Text right after another block would continue the text before it
+            if index > 0 && is_text(block) {
+                output.push('\n');
+            }
+            if !nested(self, block, output) {
+                self.block(block, output);
+            }
+        }
+    }
+
+    /// Writes the footnotes referenced so far, a footnote with more than one paragraph becomes
+    /// a ranged footnote
+    fn footnotes(&mut self, output: &mut String) {
+        for (number, content) in std::mem::take(&mut self.footnotes) {
+            separate(output);
+            if content.trim_end().contains("\n\n") {
+                output.push_str(&format!("^^ {number}\n{content}^^\n"));
+            } else {
+                output.push_str(&format!("^ {number}\n{content}"));
+            }
+        }
+    }
+}
+
+/// Writes the content of a ranged verbatim tag.
+///
+/// Norg has no way to escape its content, so lines that would end the tag early get a backslash
+/// in front of their `@end`. Returns whether there were any
+fn verbatim(content: &str, output: &mut String) -> bool {
+    let mut escaped = false;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_start();
+        if text.trim_end() == "@end" {
+            output.push_str(&line[..line.len() - text.len()]);
+            output.push('\\');
+            output.push_str(text);
+            escaped = true;
+        } else {
+            output.push_str(line);
+        }
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        output.push('\n');
+    }
+    escaped
+}
+
+fn is_text(block: &Block) -> bool {
+    matches!(block, Block::Plain(_) | Block::Para(_))
+}
+
+/// The `#start` and `#style` carryover tags of a list.
+///
+/// They hold one parameter per nesting level, levels without an ordered list get the defaults.
+/// When several lists share a level the first one is used
+fn number_tags(list: &Block) -> String {
+    let mut levels = vec![];
+    number_attributes(list, 0, &mut levels);
+
+    let mut starts: Vec<String> = levels
+        .iter()
+        .map(|attributes| attributes.map_or(1, |(start, _, _)| *start).to_string())
+        .collect();
+    let mut styles: Vec<String> = levels
+        .iter()
+        .map(|attributes| attributes.map_or_else(|| String::from("default"), number_style))
+        .collect();
+    while starts.last().map_or(false, |start| start == "1") {
+        starts.pop();
+    }
+    while styles.last().map_or(false, |style| style == "default") {
+        styles.pop();
+    }
+
+    let mut tags = String::new();
+    if !starts.is_empty() {
+        tags.push_str(&format!("#start {}\n", starts.join(" ")));
+    }
+    if !styles.is_empty() {
+        tags.push_str(&format!("#style {}\n", styles.join(" ")));
+    }
+    tags
+}
+
+/// Collects the attributes of the ordered lists in a list by their nesting level
+fn number_attributes<'a>(
+    block: &'a Block,
+    nesting: usize,
+    levels: &mut Vec<Option<&'a ListAttributes>>,
+) {
+    let items = match block {
+        Block::OrderedList(attributes, items) => {
+            if levels.len() <= nesting {
+                levels.resize(nesting + 1, None);
+            }
+            levels[nesting].get_or_insert(attributes);
+            items
+        }
+        Block::BulletList(items) => items,
+        _ => return,
+    };
+    for block in items.iter().flatten() {
+        number_attributes(block, nesting + 1, levels);
+    }
+}
+
+/// The `#style` parameter of an ordered list, a marker like `a)` the lowering reads back
+fn number_style((_, style, delim): &ListAttributes) -> String {
+    let marker = match style {
+        ListNumberStyle::Decimal => "1",
+        ListNumberStyle::LowerAlpha => "a",
+        ListNumberStyle::UpperAlpha => "A",
+        ListNumberStyle::LowerRoman => "i",
+        ListNumberStyle::UpperRoman => "I",
+        ListNumberStyle::DefaultStyle | ListNumberStyle::Example => return String::from("default"),
+    };
+    match delim {
+        ListNumberDelim::DefaultDelim => String::from(marker),
+        ListNumberDelim::Period => format!("{marker}."),
+        ListNumberDelim::OneParen => format!("{marker})"),
+        ListNumberDelim::TwoParens => format!("({marker})"),
+    }
+}
+
+/// Makes sure the next block starts after an empty line
+fn separate(output: &mut String) {
+    if !output.is_empty() && !output.ends_with("\n\n") {
+        output.push('\n');
+    }
+}
+
+/// Writes a pandoc table as the github flavoured markdown table a `@table` holds.
+///
+/// Github tables always have a header and no cell spans, the cells are written as plain text
+fn table(columns: &[ColSpec], head: &TableHead, bodies: &[TableBody], foot: &[Row]) -> String {
+    let mut rows = head.1.iter().map(row).collect::<Vec<_>>();
+    let header = if rows.is_empty() {
+        vec![String::new(); columns.len()]
+    } else {
+        rows.remove(0)
+    };
+    for (_, _, intermediate_head, body) in bodies {
+        rows.extend(intermediate_head.iter().chain(body).map(row));
+    }
+    rows.extend(foot.iter().map(row));
+
+    let mut output = table_row(&header);
+    let separators: Vec<String> = columns
+        .iter()
+        .map(|(alignment, _)| {
+            String::from(match alignment {
+                Alignment::AlignLeft => ":---",
+                Alignment::AlignRight => "---:",
+                Alignment::AlignCenter => ":---:",
+                Alignment::AlignDefault => "---",
+            })
+        })
+        .collect();
+    output.push_str(&table_row(&separators));
+    for row in rows {
+        output.push_str(&table_row(&row));
+    }
+    output
+}
+
+fn row((_, cells): &Row) -> Vec<String> {
+    cells
+        .iter()
+        .map(|(_, _, _, _, content)| {
+            plain_text(content)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .replace('|', "\\|")
+        })
+        .collect()
+}
+
+fn table_row(cells: &[String]) -> String {
+    format!("| {} |\n", cells.join(" | "))
+}
+
+/// The text of blocks without any formatting, one line per block
+pub(crate) fn plain_text(blocks: &[Block]) -> String {
+    let mut lines = vec![];
+    for block in blocks {
+        match block {
+            Block::Plain(inlines) | Block::Para(inlines) | Block::Header(_, _, inlines) => {
+                lines.push(inline::plain_text(inlines))
+            }
+            Block::LineBlock(content) => {
+                lines.extend(content.iter().map(|line| inline::plain_text(line)))
+            }
+            Block::CodeBlock(_, code) => lines.push(code.clone()),
+            Block::BlockQuote(content) | Block::Div(_, content) | Block::Figure(_, _, content) => {
+                lines.push(plain_text(content))
+            }
+            Block::OrderedList(_, items) | Block::BulletList(items) => {
+                lines.extend(items.iter().map(|item| plain_text(item)))
+            }
+            Block::DefinitionList(items) => {
+                for (term, definitions) in items {
+                    lines.push(inline::plain_text(term));
+                    lines.extend(definitions.iter().map(|definition| plain_text(definition)));
+                }
+            }
+            Block::RawBlock(_, _) | Block::HorizontalRule | Block::Table(..) | Block::Null => {}
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(content: &str) -> (String, bool) {
+        let mut output = String::new();
+        let escaped = verbatim(content, &mut output);
+        (output, escaped)
+    }
+
+    #[test]
+    fn verbatim_content_ends_with_a_line_break() {
+        assert_eq!(written("let a = 1;"), (String::from("let a = 1;\n"), false));
+        assert_eq!(written("a\nb\n"), (String::from("a\nb\n"), false));
+        assert_eq!(written(""), (String::new(), false));
+    }
+
+    #[test]
+    fn escapes_lines_that_would_end_the_tag() {
+        assert_eq!(
+            written("@code\n@end\nafter"),
+            (String::from("@code\n\\@end\nafter\n"), true)
+        );
+        assert_eq!(written("  @end  \n"), (String::from("  \\@end  \n"), true));
+        assert_eq!(written("@ending\n"), (String::from("@ending\n"), false));
+    }
+}
@@ -0,0 +1,252 @@
+use pandoc_ast::{Format, Inline, QuoteType};
+
+use super::Writer;
+
+/// The characters that open attached modifiers, links and inline link targets
+const OPENERS: [char; 14] = [
+    '*', '/', '_', '-', '!', '^', ',', '%', '`', '$', '&', '{', '[', '<',
+];
+
+impl Writer {
+    pub(super) fn inlines(&mut self, inlines: &[Inline], output: &mut String) {
+        for (index, inline) in inlines.iter().enumerate() {
+            let followed_by_space = matches!(
+                inlines.get(index + 1),
+                None | Some(Inline::Space | Inline::SoftBreak | Inline::LineBreak)
+            );
+            self.inline(inline, followed_by_space, output);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline, followed_by_space: bool, output: &mut String) {
+        match inline {
+            Inline::Str(text) => escape(text, followed_by_space, output),
+
+            Inline::Emph(content) => self.attached('/', content, output),
+            Inline::Underline(content) => self.attached('_', content, output),
+            Inline::Strong(content) => self.attached('*', content, output),
+            Inline::Strikeout(content) => self.attached('-', content, output),
+            Inline::Superscript(content) => self.attached('^', content, output),
+            Inline::Subscript(content) => self.attached(',', content, output),
+
+            Inline::SmallCaps(content) | Inline::Cite(_, content) => self.inlines(content, output),
+
+            Inline::Quoted(quote_type, content) => {
+                let quote = match quote_type {
+                    QuoteType::SingleQuote => '\'',
+                    QuoteType::DoubleQuote => '"',
+                };
+                output.push(quote);
+                self.inlines(content, output);
+                output.push(quote);
+            }
+
+            Inline::Code(_, code) => {
+                output.push('`');
+                output.push_str(code);
+                output.push('`');
+            }
+
+            Inline::Space => output.push(' '),
+
+            Inline::SoftBreak | Inline::LineBreak => output.push('\n'),
+
+            Inline::Math(_, math) => {
+                output.push('$');
+                output.push_str(math);
+                output.push('$');
+            }
+
+            Inline::RawInline(Format(format), raw) if format == "norg" => output.push_str(raw),
+
+            Inline::RawInline(Format(format), _) => {
+                self.warn(format!("can't write raw {format}, leaving it out"))
+            }
+
+            Inline::Link(_, description, (url, _)) => self.link(description, url, output),
+
+            Inline::Image(_, description, (source, _)) => {
+                self.warn("images are only written on their own line, writing a link instead");
+                self.link(description, source, output);
+            }
+
+            Inline::Note(content) => {
+                self.footnote_count += 1;
+                let number = self.footnote_count;
+                output.push_str(&format!("{{^ {number}}}"));
+
+                // The footnotes of the note itself end up inside of it
+                let pending = std::mem::take(&mut self.footnotes);
+                let mut note = String::new();
+                self.blocks(content, &mut note);
+                self.footnotes = pending;
+                self.footnotes.push((number, note));
+            }
+
+            Inline::Span((_, classes, _), content) => {
+                if classes.iter().any(|class| class == "comment") {
+                    self.attached('%', content, output)
+                } else if classes.iter().any(|class| class == "spoiler") {
+                    self.attached('!', content, output)
+                } else {
+                    self.inlines(content, output)
+                }
+            }
+        }
+    }
+
+    fn attached(&mut self, modifier: char, content: &[Inline], output: &mut String) {
+        if content.is_empty() {
+            return;
+        }
+        output.push(modifier);
+        self.inlines(content, output);
+        output.push(modifier);
+    }
+
+    /// Writes a link, links to headings of the document become heading links and links to other
+    /// norg files file links
+    fn link(&mut self, description: &[Inline], url: &str, output: &mut String) {
+        let (target, text) = if let Some(id) = url.strip_prefix('#') {
+            match self.headings.get(id) {
+                Some((level, title)) => (
+                    format!("{{{} {title}}}", "*".repeat((*level).max(1) as usize)),
+                    title.clone(),
+                ),
+                None => (format!("{{# {id}}}"), id.to_owned()),
+            }
+        } else if let Some(path) = url.strip_suffix(".norg").filter(|_| !url.contains("://")) {
+            (format!("{{:{path}:}}"), path.to_owned())
+        } else {
+            (format!("{{{url}}}"), url.to_owned())
+        };
+
+        output.push_str(&target);
+        if !description.is_empty() && plain_text(description) != text {
+            output.push('[');
+            self.inlines(description, output);
+            output.push(']');
+        }
+    }
+}
+
+/// Writes text, escaping the characters that would start norg markup.
+///
+/// Modifiers only open after whitespace or punctuation and in front of something else than
+/// whitespace, so most characters can stay as they are
+fn escape(text: &str, followed_by_space: bool, output: &mut String) {
+    let at_line_start = output.is_empty() || output.ends_with('\n');
+    let mut chars = text.chars().peekable();
+    let mut first = true;
+
+    while let Some(c) = chars.next() {
+        let previous = output.chars().last();
+        let next_is_space = match chars.peek() {
+            Some(next) => next.is_whitespace(),
+            None => followed_by_space,
+        };
+
+        let opens = OPENERS.contains(&c)
+            && !next_is_space
+            && previous.map_or(true, |previous| {
+                previous.is_whitespace() || (!previous.is_alphanumeric() && previous != c)
+            });
+        let detaches = first && at_line_start && starts_detached(text, followed_by_space);
+
+        if c == '\\' || opens || detaches {
+            output.push('\\');
+        }
+        output.push(c);
+        first = false;
+    }
+}
+
+/// Whether text at the start of a line would be read as a detached modifier or a tag, like the
+/// `*` of a heading or the `.` of `.image`
+fn starts_detached(text: &str, followed_by_space: bool) -> bool {
+    let Some(first) = text.chars().next() else {
+        return false;
+    };
+    match first {
+        '*' | '-' | '~' | '>' | '$' | '^' | '%' | '_' | '=' => {
+            followed_by_space && text.chars().all(|c| c == first)
+        }
+        '@' | '.' | '#' | '+' | '|' => text.len() > 1,
+        _ => false,
+    }
+}
+
+/// The text of inlines without any formatting
+pub(crate) fn plain_text(inlines: &[Inline]) -> String {
+    let mut output = String::new();
+    push_plain_text(inlines, &mut output);
+    output
+}
+
+fn push_plain_text(inlines: &[Inline], output: &mut String) {
+    for inline in inlines {
+        match inline {
+            Inline::Str(text) | Inline::Code(_, text) | Inline::Math(_, text) => {
+                output.push_str(text)
+            }
+            Inline::Space | Inline::SoftBreak | Inline::LineBreak => output.push(' '),
+            Inline::Emph(content)
+            | Inline::Underline(content)
+            | Inline::Strong(content)
+            | Inline::Strikeout(content)
+            | Inline::Superscript(content)
+            | Inline::Subscript(content)
+            | Inline::SmallCaps(content)
+            | Inline::Quoted(_, content)
+            | Inline::Cite(_, content)
+            | Inline::Link(_, content, _)
+            | Inline::Image(_, content, _)
+            | Inline::Span(_, content) => push_plain_text(content, output),
+            Inline::RawInline(_, _) | Inline::Note(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escaped(before: &str, text: &str, followed_by_space: bool) -> String {
+        let mut output = String::from(before);
+        escape(text, followed_by_space, &mut output);
+        output[before.len()..].to_owned()
+    }
+
+    #[test]
+    fn escapes_openers_of_attached_modifiers() {
+        assert_eq!(escaped("", "*bold*", true), "\\*bold*");
+        assert_eq!(escaped("some ", "/path", true), "\\/path");
+        assert_eq!(escaped("", "{link}", true), "\\{link}");
+    }
+
+    #[test]
+    fn leaves_characters_that_open_nothing() {
+        assert_eq!(escaped("", "a*b", true), "a*b");
+        assert_eq!(escaped("2 ", "*", true), "*");
+        assert_eq!(escaped("", "snake_case", true), "snake_case");
+    }
+
+    #[test]
+    fn escapes_backslashes() {
+        assert_eq!(escaped("", "C:\\dir", true), "C:\\\\dir");
+    }
+
+    #[test]
+    fn escapes_detached_modifiers_at_line_start() {
+        assert_eq!(escaped("", "*", true), "\\*");
+        assert_eq!(escaped("text\n", "~~", true), "\\~~");
+        assert_eq!(escaped("", "@code", true), "\\@code");
+        assert_eq!(escaped("", "#tag", true), "\\#tag");
+    }
+
+    #[test]
+    fn leaves_detached_modifiers_within_a_line() {
+        assert_eq!(escaped("a ", "~~", true), "~~");
+        assert_eq!(escaped("a ", "@code", true), "@code");
+    }
+}
@@ -0,0 +1,109 @@
+//! Writes a pandoc AST as norg source, e.g. to move notes from other formats pandoc can read
+
+use std::collections::HashMap;
+
+use pandoc_ast::{Block, Map, MetaValue, Pandoc};
+
+use crate::Diagnostic;
+
+pub(crate) mod block;
+pub(crate) mod inline;
+
+/// Norg source written from a pandoc AST together with everything that couldn't be written
+pub struct Written {
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+struct Writer {
+    /// The titles and levels of the headings by their id, so links to them become heading links
+    headings: HashMap<String, (i64, String)>,
+    /// The footnotes of the current block, they are written right after it
+    footnotes: Vec<(usize, String)>,
+    /// The number of footnotes written so far
+    footnote_count: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Writer {
+    fn warn(&mut self, message: impl Into<String>) {
+        self.diagnostics
+            .push(Diagnostic::warning_without_span(message));
+    }
+}
+
+/// Writes a pandoc AST as norg source.
+///
+/// Elements norg has no syntax for are written as their content, or left out with a warning
+pub fn write_norg(pandoc: &Pandoc) -> Written {
+    let mut writer = Writer {
+        headings: HashMap::new(),
+        footnotes: vec![],
+        footnote_count: 0,
+        diagnostics: vec![],
+    };
+    collect_headings(&pandoc.blocks, &mut writer.headings);
+
+    let mut output = metadata(&mut writer, &pandoc.meta);
+    writer.blocks(&pandoc.blocks, &mut output);
+
+    Written {
+        source: output,
+        diagnostics: writer.diagnostics,
+    }
+}
+
+/// Collects the headings in the document and the sections pandoc wraps them in
+fn collect_headings(blocks: &[Block], headings: &mut HashMap<String, (i64, String)>) {
+    for block in blocks {
+        match block {
+            Block::Header(level, (id, _, _), title) if !id.is_empty() => {
+                headings.insert(id.clone(), (*level, inline::plain_text(title)));
+            }
+            Block::Div(_, content) => collect_headings(content, headings),
+            _ => {}
+        }
+    }
+}
+
+/// Writes the pandoc metadata into a `@document.meta` tag.
+///
+/// It only holds one line per key, so lists are joined with commas
+fn metadata(writer: &mut Writer, meta: &Map<String, MetaValue>) -> String {
+    let mut lines = String::new();
+    for (key, value) in meta {
+        let key = match key.as_str() {
+            "author" => "authors",
+            key => key,
+        };
+        match meta_text(value) {
+            Some(value) => lines.push_str(&format!("{key}: {value}\n")),
+            None => writer.warn(format!(
+                "the metadata {key} can't be written on a single line, leaving it out"
+            )),
+        }
+    }
+
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!("@document.meta\n{lines}@end\n\n")
+    }
+}
+
+fn meta_text(value: &MetaValue) -> Option<String> {
+    let text = match value {
+        MetaValue::MetaString(text) => text.clone(),
+        MetaValue::MetaBool(value) => value.to_string(),
+        MetaValue::MetaInlines(inlines) => inline::plain_text(inlines),
+        MetaValue::MetaBlocks(blocks) => block::plain_text(blocks),
+        MetaValue::MetaList(values) => values
+            .iter()
+            .map(meta_text)
+            .collect::<Option<Vec<String>>>()?
+            .join(", "),
+        MetaValue::MetaMap(_) => return None,
+    };
+
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
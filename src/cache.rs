@@ -0,0 +1,143 @@
+//! Remembers what converting a directory was based on, so the next run only converts the files
+//! that changed
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    fs,
+    hash::{Hash, Hasher},
+    io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the cache file in the output directory
+pub const CACHE_FILE: &str = ".norg-pandoc-cache";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Cache {
+    /// A hash of everything besides the files that changes the output, like the options and the
+    /// pandoc version. When it changes every file is converted again
+    pub settings: String,
+    /// The files that were converted by their path relative to the input directory, files that
+    /// failed aren't in here
+    pub files: BTreeMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Entry {
+    /// The hash of the content the output was converted from
+    pub hash: String,
+    /// The hashes of the files it links to at that time, empty for files that didn't exist. The
+    /// paths are relative to the input directory, unless the file is outside of it
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl Cache {
+    /// Reads the cache file, a missing or broken one is an empty cache
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, content)
+    }
+
+    /// Whether the output of a file is up to date, because neither it nor the files it links to
+    /// changed since it was converted
+    pub fn is_fresh(&self, file: &str, hash: &str, input: &Path) -> bool {
+        self.files.get(file).map_or(false, |entry| {
+            entry.hash == hash
+                && entry
+                    .dependencies
+                    .iter()
+                    .all(|(dependency, hash)| hash_file(&input.join(dependency)) == *hash)
+        })
+    }
+}
+
+/// Hashes the content of a file, it's empty if the file can't be read.
+///
+/// The hasher of the standard library can change between releases, that only means the next
+/// run converts everything
+pub fn hash_file(path: &Path) -> String {
+    match fs::read(path) {
+        Ok(content) => hash(content),
+        Err(_) => String::new(),
+    }
+}
+
+pub fn hash(value: impl Hash) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// An empty directory for a test, whatever an earlier run left in it is removed
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("norg-pandoc-cache-{name}"));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn cache(dependencies: &[(&str, String)]) -> Cache {
+        let mut cache = Cache::default();
+        cache.files.insert(
+            String::from("file.norg"),
+            Entry {
+                hash: hash("content"),
+                dependencies: dependencies
+                    .iter()
+                    .map(|(path, hash)| (path.to_string(), hash.clone()))
+                    .collect(),
+            },
+        );
+        cache
+    }
+
+    #[test]
+    fn fresh_if_the_file_is_unchanged() {
+        let input = directory("unchanged");
+        let cache = cache(&[]);
+        assert!(cache.is_fresh("file.norg", &hash("content"), &input));
+        assert!(!cache.is_fresh("file.norg", &hash("changed"), &input));
+        assert!(!cache.is_fresh("other.norg", &hash("content"), &input));
+    }
+
+    #[test]
+    fn stale_if_a_dependency_changed() {
+        let input = directory("dependency");
+        fs::write(input.join("linked.norg"), "linked").unwrap();
+        let cache = cache(&[("linked.norg", hash_file(&input.join("linked.norg")))]);
+        assert!(cache.is_fresh("file.norg", &hash("content"), &input));
+
+        fs::write(input.join("linked.norg"), "changed").unwrap();
+        assert!(!cache.is_fresh("file.norg", &hash("content"), &input));
+    }
+
+    #[test]
+    fn stale_if_a_missing_dependency_appears() {
+        let input = directory("missing");
+        let cache = cache(&[("linked.norg", String::new())]);
+        assert!(cache.is_fresh("file.norg", &hash("content"), &input));
+
+        fs::write(input.join("linked.norg"), "linked").unwrap();
+        assert!(!cache.is_fresh("file.norg", &hash("content"), &input));
+    }
+}
@@ -0,0 +1,161 @@
+//! The project configuration, read from a `norg-pandoc.toml` file.
+//!
+//! Everything in it is a default, the flags given on the command line override it
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use pandoc_ast::MetaValue;
+use serde::Deserialize;
+
+pub const CONFIG_FILE: &str = "norg-pandoc.toml";
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The directory the config file is in, relative paths in it are relative to that
+    #[serde(skip)]
+    pub directory: PathBuf,
+
+    /// The format to convert to when `--to` isn't given
+    pub to: Option<String>,
+    pub output: Option<PathBuf>,
+    pub jobs: Option<usize>,
+    /// The pandoc executable to run
+    pub pandoc: Option<String>,
+    /// Passed on to pandoc for every format, before the arguments of the profile
+    pub pandoc_args: Vec<String>,
+
+    /// Globs of the files to convert in a directory, relative to it. Without any every file is
+    /// converted
+    pub include: Vec<String>,
+    /// Globs of the files in a directory that aren't converted, even if they are included
+    pub exclude: Vec<String>,
+
+    pub workspace_root: Option<PathBuf>,
+    /// The directories `$name/` in links refers to by their name
+    pub workspaces: HashMap<String, PathBuf>,
+
+    /// `clamp`, `div` or `rebase`
+    pub deep_headings: Option<String>,
+    pub heading_offset: Option<i64>,
+    pub source_positions: bool,
+    pub deny_broken_links: bool,
+    /// The extension of links to other norg files, `target-format`, `none` or the extension
+    /// itself
+    pub link_extension: Option<String>,
+
+    /// Metadata every document gets, unless it sets the key itself
+    pub metadata: toml::Table,
+
+    /// Pandoc arguments by the format they are used for, e.g. `[profiles.html]`
+    pub profiles: HashMap<String, Profile>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// Passed on to pandoc before the arguments given on the command line
+    pub pandoc_args: Vec<String>,
+}
+
+impl Config {
+    /// Reads the config file in the first directory that has one, without one the config is
+    /// empty
+    pub fn find(directories: &[&Path]) -> Result<Self, String> {
+        for directory in directories {
+            let path = directory.join(CONFIG_FILE);
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(error) if error.kind() == ErrorKind::NotFound => continue,
+                Err(error) => {
+                    return Err(format!("{}: couldn't read file: {error}", path.display()))
+                }
+            };
+            let mut config: Config =
+                toml::from_str(&content).map_err(|error| format!("{}: {error}", path.display()))?;
+            config.directory = directory.to_path_buf();
+            return Ok(config);
+        }
+
+        Ok(Config::default())
+    }
+
+    /// Resolves a path from the config file against its directory
+    pub fn path(&self, path: &Path) -> PathBuf {
+        self.directory.join(path)
+    }
+
+    /// The profile of a format, formats with extensions like `markdown+smart` use the profile of
+    /// the format without them if they have none of their own
+    pub fn profile(&self, format: &str) -> Option<&Profile> {
+        self.profiles.get(format).or_else(|| {
+            let base = format.split(['+', '-']).next()?;
+            self.profiles.get(base)
+        })
+    }
+
+    /// The files of a directory that are converted
+    pub fn files(&self) -> Result<Files, String> {
+        Ok(Files {
+            include: (!self.include.is_empty())
+                .then(|| glob_set(&self.include))
+                .transpose()?,
+            exclude: glob_set(&self.exclude)?,
+        })
+    }
+
+    /// The metadata defaults as pandoc metadata
+    pub fn metadata(&self) -> Vec<(String, MetaValue)> {
+        self.metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), meta_value(value)))
+            .collect()
+    }
+}
+
+/// The include and exclude globs of the config
+pub struct Files {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Files {
+    /// Whether a file is converted, the path is relative to the input directory
+    pub fn contains(&self, path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .map_or(true, |include| include.is_match(path))
+            && !self.exclude.is_match(path)
+    }
+}
+
+fn glob_set(globs: &[String]) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(|error| format!("{CONFIG_FILE}: {error}"))?);
+    }
+    builder
+        .build()
+        .map_err(|error| format!("{CONFIG_FILE}: {error}"))
+}
+
+fn meta_value(value: &toml::Value) -> MetaValue {
+    match value {
+        toml::Value::String(text) => MetaValue::MetaString(text.clone()),
+        toml::Value::Boolean(value) => MetaValue::MetaBool(*value),
+        toml::Value::Array(values) => MetaValue::MetaList(values.iter().map(meta_value).collect()),
+        toml::Value::Table(table) => MetaValue::MetaMap(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), Box::new(meta_value(value))))
+                .collect(),
+        ),
+        value => MetaValue::MetaString(value.to_string()),
+    }
+}
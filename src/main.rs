@@ -1,92 +1,379 @@
 use std::{
-    fs,
-    io::Write,
-    path::{Path, PathBuf},
-    process::{exit, Command, Stdio},
-    sync::Arc,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    process::{exit, Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use clap::{arg, command, Args, Parser};
+use clap::{arg, command, ArgMatches, Args, Parser, Subcommand};
+use norg_pandoc_ast::{
+    render_html, write_norg, Converter, DeepHeadings, LinkExtension, ParseOptions, Severity,
+};
+use pandoc_ast::MetaValue;
 use walkdir::WalkDir;
 
+use crate::{
+    cache::{Cache, Entry, CACHE_FILE},
+    config::{Config, Files, CONFIG_FILE},
+};
+
+mod cache;
+mod config;
+mod watch;
+
 const PANDOC_PATH: Option<&str> = option_env!("PANDOC_PATH");
 
+thread_local! {
+    /// Every thread converts with its own parser, it's created for the first file
+    static CONVERTER: RefCell<Option<Converter>> = const { RefCell::new(None) };
+}
+
 /// This is a cli tool to convert a norg tool to any pandoc supported file format.
 ///
-/// It uses pandoc under the hood
+/// It uses pandoc under the hood. Defaults for the arguments can be set in a `norg-pandoc.toml` in
+/// the input directory or workspace root
 #[derive(Parser)]
 struct Arguments {
-    /// The file format to convert to
+    /// The file format to convert to, required unless the config file sets it
+    ///
+    /// `json` writes the pandoc AST without running pandoc
     #[arg(short, long)]
-    to: String,
+    to: Option<String>,
+
+    /// Convert from this pandoc format to norg instead, e.g. `markdown` or `org`
+    ///
+    /// In a directory only the files with the usual extensions of the format are converted
+    #[arg(short, long, conflicts_with = "to")]
+    from: Option<String>,
 
     /// The output file/directory name
     ///
-    /// The default behaviour will place output files with the same name right next to input files
+    /// The default behaviour will place output files with the same name right next to input files,
+    /// `-` writes to stdout, which is also the default when reading from stdin
     #[arg(short, long)]
     output: Option<PathBuf>,
 
+    /// The directory `$/` in links refers to
+    ///
+    /// Defaults to the input directory, the directory of the output file or the current directory
+    /// when reading from stdin
+    #[arg(long)]
+    workspace_root: Option<PathBuf>,
+
     /// The maximum number of threads to use when parsing multiple files
     ///
     /// Defaults to double the number of CPUs
     #[arg(short, long)]
     jobs: Option<usize>,
 
-    /// The input file/directory
+    /// How to handle headings nested deeper than 6 levels
+    ///
+    /// `clamp` treats them like 6th level headings, `div` replaces them with a bold paragraph in a
+    /// div with a `level-N` class and `rebase` shifts all headings so the shallowest one becomes a
+    /// 1st level heading
+    #[arg(long, value_parser = ["clamp", "div", "rebase"])]
+    deep_headings: Option<String>,
+
+    /// Shifts all heading levels by this amount
+    ///
+    /// An offset of 1 turns `*` headings into 2nd level headings, e.g. when embedding notes into a
+    /// larger document
+    #[arg(long, allow_negative_numbers = true)]
+    heading_offset: Option<i64>,
+
+    /// What writes the output, `native` renders html without pandoc for fast previews
+    ///
+    /// The native engine only supports `--to html` and ignores the arguments meant for pandoc
+    #[arg(long, default_value = "pandoc", value_parser = ["pandoc", "native"])]
+    engine: String,
+
+    /// Passed on to pandoc to decide what the 1st level headings become
+    #[arg(long, value_parser = ["default", "section", "chapter", "part"])]
+    top_level_division: Option<String>,
+
+    /// Treat warnings like errors, files with warnings won't be converted
+    #[arg(long)]
+    deny_warnings: bool,
+
+    /// Add `data-pos` attributes with the source positions to the generated elements
+    #[arg(long, overrides_with = "no_source_positions")]
+    source_positions: bool,
+
+    /// Leave out the source positions, even if the config file adds them
+    #[arg(long, overrides_with = "source_positions")]
+    no_source_positions: bool,
+
+    /// Fail files with links to headings, link targets or anchors that don't exist
+    #[arg(long, overrides_with = "no_deny_broken_links")]
+    deny_broken_links: bool,
+
+    /// Only warn about broken links, even if the config file denies them
+    #[arg(long, overrides_with = "deny_broken_links")]
+    no_deny_broken_links: bool,
+
+    /// Print the pandoc AST as json to stdout instead of converting, one line per file in the
+    /// order of their paths
+    ///
+    /// Pandoc isn't needed for this, `--to` only decides the extension of links to other files
+    /// and defaults to html
+    #[arg(long, conflicts_with_all = ["from", "output", "engine"])]
+    emit_ast: bool,
+
+    /// Keep converting the files of the input directory again when they change
+    ///
+    /// Files linking to a changed file are converted again too, the outputs of deleted files are
+    /// removed
+    #[arg(short, long, conflicts_with = "emit_ast")]
+    watch: bool,
+
+    /// Convert every file of the input directory, even the ones that didn't change since the
+    /// last run
+    #[arg(long)]
+    force: bool,
+
+    /// The pandoc API version of the generated AST, e.g. `1.23.1`
+    ///
+    /// By default it's asked from the installed pandoc when pandoc is used for the conversion, and
+    /// the version the library was written for otherwise
+    #[arg(long)]
+    api_version: Option<String>,
+
+    /// The input file/directory, `-` reads from stdin
     input: PathBuf,
 }
 
+// Subcommands that check the conversion instead of converting
+#[derive(Subcommand)]
+enum Commands {
+    /// Converts norg files to pandoc, writes them back as norg and reports everything that
+    /// doesn't survive that
+    ///
+    /// Exits with 4 if anything was lost or changed, so it can be used as a regression check
+    RoundTrip {
+        /// The norg file or directory to check
+        input: PathBuf,
+    },
+}
+
 fn main() {
     let cli = command!();
     let cli = Arguments::augment_args(cli)
         .arg(arg!([PANDOC_ARGS] ... "arguments to pass on to pandonc").last(true));
+    let cli = Commands::augment_subcommands(cli)
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true);
     let matches = cli.get_matches();
 
+    if let Some(("round-trip", matches)) = matches.subcommand() {
+        let input = matches.get_one::<PathBuf>("input").unwrap();
+        if !input.exists() {
+            eprintln!("Input path not found");
+            exit(1);
+        }
+        if !round_trip(input) {
+            exit(4);
+        }
+        return;
+    }
+
     let input = matches.get_one::<PathBuf>("input").unwrap().to_owned();
     let output = matches.get_one::<PathBuf>("output").map(|v| v.to_owned());
-    let to = matches.get_one::<String>("to").unwrap().to_owned();
-    let pandoc_args: Option<String> = matches
+    let workspace_root = matches
+        .get_one::<PathBuf>("workspace_root")
+        .map(|v| v.to_owned());
+    let from = matches.get_one::<String>("from").map(|v| v.to_owned());
+    let to = matches.get_one::<String>("to").map(|v| v.to_owned());
+    let emit_ast = matches.get_flag("emit_ast");
+    let cli_pandoc_args: Vec<String> = matches
         .get_many::<String>("PANDOC_ARGS")
-        .map(|v| v.map(|v| v.to_owned()).collect::<Vec<String>>().join(" "));
+        .map_or(vec![], |v| v.map(|v| v.to_owned()).collect());
     let jobs = matches.get_one::<usize>("jobs").map(|v| v.to_owned());
+    let deep_headings = matches.get_one::<String>("deep_headings").cloned();
+    let heading_offset = matches.get_one::<i64>("heading_offset").copied();
+    let top_level_division = matches
+        .get_one::<String>("top_level_division")
+        .map(|v| v.to_owned());
+    let native = matches.get_one::<String>("engine").unwrap() == "native";
+    let api_version = matches.get_one::<String>("api_version").map(|version| {
+        version
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .unwrap_or_else(|_| {
+                eprintln!("The API version has to be numbers separated by dots, like 1.23.1");
+                exit(1);
+            })
+    });
+    let source_positions = flag(&matches, "source_positions");
+    let deny_warnings = matches.get_flag("deny_warnings");
+    let deny_broken_links = flag(&matches, "deny_broken_links");
+    let watch = matches.get_flag("watch");
+    let force = matches.get_flag("force");
 
-    if !input.exists() {
+    let stdin = is_standard_stream(&input);
+    if !stdin && !input.exists() {
         eprintln!("Input path not found");
         exit(1);
     }
 
-    let api_version = get_api_version();
+    if watch && (stdin || input.is_file()) {
+        eprintln!("Only directories can be watched");
+        exit(1);
+    }
 
-    if input.is_file() {
+    let input_directory = if stdin {
+        PathBuf::from(".")
+    } else if input.is_dir() {
+        input.clone()
+    } else {
+        input.parent().unwrap().to_path_buf()
+    };
+    let mut config_directories = vec![input_directory.as_path()];
+    if let Some(workspace_root) = &workspace_root {
+        config_directories.push(workspace_root);
+    }
+    let config = Config::find(&config_directories).unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        exit(1);
+    });
+
+    // The command line overrides the config
+    let to = match (&from, to.or(config.to.clone())) {
+        (Some(_), _) => String::from("norg"),
+        (None, Some(to)) => to,
+        (None, None) if emit_ast => String::from("html"),
+        (None, None) => {
+            eprintln!("No format to convert to, use --to or set `to` in {CONFIG_FILE}");
+            exit(1);
+        }
+    };
+    // Output from stdin goes to stdout, unless it's explicitly asked for elsewhere
+    let output = match output {
+        Some(output) => Some(output),
+        None if stdin => None,
+        None => config.output.as_deref().map(|output| config.path(output)),
+    };
+    let workspace_root = workspace_root.or(config
+        .workspace_root
+        .as_deref()
+        .map(|workspace_root| config.path(workspace_root)));
+    let jobs = jobs.or(config.jobs);
+    let deny_broken_links = deny_broken_links.unwrap_or(config.deny_broken_links);
+    let deep_headings = match deep_headings.or(config.deep_headings.clone()).as_deref() {
+        None | Some("clamp") => DeepHeadings::Clamp,
+        Some("div") => DeepHeadings::Div,
+        Some("rebase") => DeepHeadings::Rebase,
+        Some(deep_headings) => {
+            eprintln!("error: {CONFIG_FILE}: unknown deep-headings `{deep_headings}`");
+            exit(1);
+        }
+    };
+    let heading_offset = heading_offset.or(config.heading_offset).unwrap_or(0);
+    let source_positions = source_positions.unwrap_or(config.source_positions);
+    let link_extension = match config.link_extension.as_deref() {
+        None | Some("target-format") => LinkExtension::TargetFormat,
+        Some("none") => LinkExtension::None,
+        Some(extension) => LinkExtension::Custom(extension.trim_start_matches('.').to_owned()),
+    };
+    let pandoc = config
+        .pandoc
+        .clone()
+        .unwrap_or_else(|| PANDOC_PATH.unwrap_or("pandoc").to_owned());
+    // The profile comes after the general arguments and before the command line, so pandoc lets
+    // the more specific ones override the others
+    let mut pandoc_args = config.pandoc_args.clone();
+    if let Some(profile) = config.profile(&to) {
+        pandoc_args.extend(profile.pandoc_args.iter().cloned());
+    }
+    pandoc_args.extend(cli_pandoc_args);
+    let files = config.files().unwrap_or_else(|error| {
+        eprintln!("error: {error}");
+        exit(1);
+    });
+
+    if native && (to != "html" || from.is_some()) {
+        eprintln!("The native engine can only convert norg to html");
+        exit(1);
+    }
+
+    let writer = if emit_ast {
+        Writer::Stdout
+    } else if native {
+        Writer::Native
+    } else if to == "json" && from.is_none() {
+        Writer::Json
+    } else {
+        Writer::Pandoc
+    };
+
+    let mut options = ParseOptions::new();
+    if let Some(api_version) = api_version {
+        options = options.api_version(api_version);
+    } else if writer == Writer::Pandoc && from.is_none() {
+        options = options.api_version(get_api_version(&pandoc));
+    }
+    for (name, root) in &config.workspaces {
+        options = options.workspace(name, config.path(root));
+    }
+    let mut settings = Settings {
+        options: options
+            .target_format(&to)
+            .link_extension(link_extension)
+            .deep_headings(deep_headings)
+            .heading_offset(heading_offset)
+            .source_positions(source_positions),
+        to,
+        from,
+        writer,
+        pandoc,
+        pandoc_args,
+        top_level_division,
+        metadata: config.metadata(),
+        deny_warnings,
+        deny_broken_links,
+        dependencies: Mutex::new(HashMap::new()),
+    };
+
+    if stdin || input.is_file() {
         let output = match output {
+            Some(output) if stdin && output.is_dir() => {
+                eprintln!("When the input is stdin, the output can't be a directory");
+                exit(2);
+            }
+            Some(output) if stdin || is_standard_stream(&output) || output.is_file() => output,
             Some(mut output) => {
-                if output.is_file() {
-                    output
-                } else {
-                    let mut filename = PathBuf::new();
-                    filename.push(input.file_name().unwrap());
-                    filename.set_extension(&to);
-                    output.push(filename);
-                    output
-                }
+                let mut filename = PathBuf::new();
+                filename.push(input.file_name().unwrap());
+                filename.set_extension(&settings.to);
+                output.push(filename);
+                output
             }
+            None if stdin => PathBuf::from("-"),
             None => {
                 let mut output = input.clone();
-                output.set_extension(&to);
+                output.set_extension(&settings.to);
                 output
             }
         };
-        parse_file(
-            &input,
-            &to,
-            pandoc_args.as_deref(),
-            &output,
-            api_version,
-            output.parent().unwrap(),
-        );
+        let workspace_root = match workspace_root {
+            Some(workspace_root) => workspace_root,
+            None if stdin => PathBuf::from("."),
+            None if is_standard_stream(&output) => input.parent().unwrap().to_path_buf(),
+            None => output.parent().unwrap().to_path_buf(),
+        };
+        settings.options = settings.options.workspace_root(workspace_root);
+        if !convert_file(&input, &output, &settings) {
+            exit(4);
+        }
     } else {
         let output = if let Some(output) = output {
-            if output.is_file() {
+            if output.is_file() || is_standard_stream(&output) {
                 eprintln!("When the input is a directory, the output can't be a file");
                 exit(2);
             }
@@ -95,8 +382,7 @@ fn main() {
         } else {
             input.clone()
         };
-        let workspace_root = Arc::new(input.clone());
-        let directory_walker = WalkDir::new(&input).into_iter();
+        let directory_walker = WalkDir::new(&input).sort_by_file_name().into_iter();
         let thread_pool = if let Some(jobs) = jobs {
             rusty_pool::Builder::new()
                 .name("norg_pandoc".to_string())
@@ -108,47 +394,258 @@ fn main() {
                 .name("norg_pandoc".to_string())
                 .build()
         };
-        let to = Arc::new(to);
-        let pandoc_args = Arc::new(pandoc_args);
+        settings.options = settings
+            .options
+            .workspace_root(workspace_root.unwrap_or_else(|| input.clone()));
+
+        // Printing the AST doesn't leave any files that could be up to date
+        let use_cache = settings.writer != Writer::Stdout;
+        let cache_file = output.join(CACHE_FILE);
+        // The fingerprint runs pandoc for its version, so only when it's needed
+        let fingerprint = if use_cache {
+            settings.fingerprint()
+        } else {
+            String::new()
+        };
+        // Forcing converts every file, but still writes the cache for the next run
+        let mut cache = if use_cache && !force {
+            Cache::load(&cache_file)
+        } else {
+            Cache::default()
+        };
+        if cache.settings != fingerprint {
+            cache.files.clear();
+        }
+        let root = normalize(&input);
+        let mut cached = BTreeMap::new();
+
+        let settings = Arc::new(settings);
+        let failed = Arc::new(AtomicBool::new(false));
+        let converted = Arc::new(Mutex::new(vec![]));
+        let sources = Sources {
+            extensions: match &settings.from {
+                Some(from) => source_extensions(from),
+                None => vec![String::from("norg")],
+            },
+            files,
+        };
         for entry in directory_walker {
             let entry = entry.unwrap().path().to_path_buf();
-            if entry.is_file()
-                && entry
-                    .extension()
-                    .map_or(false, |e| e.to_str().map_or(false, |e| e == "norg"))
-            {
-                let mut output = output.clone();
-                output.push(entry.strip_prefix(&input).unwrap());
-                output.set_extension(&*to);
-                let to = to.clone();
-                let pandoc_args = pandoc_args.clone();
-                let api_version = api_version.clone();
-                let workspace_root = workspace_root.clone();
-                thread_pool.execute(move || {
-                    parse_file(
-                        &entry,
-                        &to,
-                        pandoc_args.as_deref(),
-                        &output,
-                        api_version,
-                        &workspace_root,
+            let relative = entry.strip_prefix(&input).unwrap();
+            if entry.is_file() && sources.contains(relative) {
+                let output = output_file(&output, relative, &settings.to);
+                let key = relative.to_string_lossy().into_owned();
+                let hash = if use_cache {
+                    cache::hash_file(&entry)
+                } else {
+                    String::new()
+                };
+                if use_cache && !force && output.exists() && cache.is_fresh(&key, &hash, &root) {
+                    // Watching needs the links of the skipped files too
+                    let entry_cache = cache.files.remove(&key).unwrap();
+                    settings.dependencies.lock().unwrap().insert(
+                        normalize(&entry),
+                        entry_cache
+                            .dependencies
+                            .keys()
+                            .map(|dependency| root.join(dependency))
+                            .collect(),
                     );
+                    cached.insert(key, entry_cache);
+                    continue;
+                }
+
+                // Printed ASTs come in the order of the paths, so they are converted one by one
+                if settings.writer == Writer::Stdout {
+                    if !convert_file(&entry, &output, &settings) {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    continue;
+                }
+
+                let settings = settings.clone();
+                let failed = failed.clone();
+                let converted = converted.clone();
+                thread_pool.execute(move || {
+                    if convert_file(&entry, &output, &settings) {
+                        converted
+                            .lock()
+                            .unwrap()
+                            .push((key, normalize(&entry), hash));
+                    } else {
+                        failed.store(true, Ordering::Relaxed);
+                    }
                 });
             }
         }
         thread_pool.join();
+
+        if use_cache {
+            let dependencies = settings.dependencies.lock().unwrap();
+            for (key, file, hash) in converted.lock().unwrap().drain(..) {
+                let dependencies = dependencies
+                    .get(&file)
+                    .into_iter()
+                    .flatten()
+                    .map(|dependency| {
+                        let key = dependency.strip_prefix(&root).unwrap_or(dependency);
+                        (
+                            key.to_string_lossy().into_owned(),
+                            cache::hash_file(dependency),
+                        )
+                    })
+                    .collect();
+                cached.insert(key, Entry { hash, dependencies });
+            }
+            let cache = Cache {
+                settings: fingerprint,
+                files: cached,
+            };
+            if let Err(error) = cache.save(&cache_file) {
+                eprintln!(
+                    "{}: warning: couldn't write the cache: {error}",
+                    cache_file.display()
+                );
+            }
+        }
+
+        if watch {
+            watch::watch(&input, &output, &sources, &settings);
+            exit(1);
+        }
+        if failed.load(Ordering::Relaxed) {
+            exit(4);
+        }
+    }
+}
+
+/// A flag that can be turned off with `--no-<flag>`, `None` if neither was given
+fn flag(matches: &ArgMatches, name: &str) -> Option<bool> {
+    if matches.get_flag(name) {
+        Some(true)
+    } else if matches.get_flag(&format!("no_{name}")) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// The settings that are the same for every converted file
+struct Settings {
+    to: String,
+    /// The format to convert to norg from, if not converting norg
+    from: Option<String>,
+    writer: Writer,
+    /// The pandoc executable
+    pandoc: String,
+    /// The arguments of the profile for the format and the command line, each one on its own
+    pandoc_args: Vec<String>,
+    top_level_division: Option<String>,
+    /// Metadata the documents get if they don't set it themselves
+    metadata: Vec<(String, MetaValue)>,
+    options: ParseOptions,
+    deny_warnings: bool,
+    deny_broken_links: bool,
+    /// The norg files each converted file links to, by the normalized path of the file. It's
+    /// filled in while converting
+    dependencies: Mutex<HashMap<PathBuf, Vec<PathBuf>>>,
+}
+
+impl Settings {
+    /// A hash of everything that changes the output besides the converted file itself
+    fn fingerprint(&self) -> String {
+        let pandoc_version = if self.writer == Writer::Pandoc || self.from.is_some() {
+            get_pandoc_version(&self.pandoc)
+        } else {
+            String::new()
+        };
+        cache::hash(format!(
+            "{} {pandoc_version} {:?} {} {:?} {:?} {:?} {:?} {:?} {} {}",
+            env!("CARGO_PKG_VERSION"),
+            self.options,
+            self.to,
+            self.from,
+            self.writer,
+            self.pandoc_args,
+            self.top_level_division,
+            self.metadata,
+            self.deny_warnings,
+            self.deny_broken_links,
+        ))
+    }
+}
+
+/// Which files of an input directory are converted
+struct Sources {
+    extensions: Vec<String>,
+    files: Files,
+}
+
+impl Sources {
+    /// Whether a file is converted, the path is relative to the input directory
+    fn contains(&self, file: &Path) -> bool {
+        file.extension().map_or(false, |e| {
+            e.to_str().map_or(false, |e| {
+                self.extensions.iter().any(|extension| extension == e)
+            })
+        }) && self.files.contains(file)
+    }
+}
+
+/// Where the output of a file in the input directory goes
+fn output_file(output: &Path, file: &Path, to: &str) -> PathBuf {
+    let mut output = output.join(file);
+    output.set_extension(to);
+    output
+}
+
+/// What writes the converted norg files
+#[derive(Debug, PartialEq)]
+enum Writer {
+    Pandoc,
+    /// The html renderer of the library, without pandoc
+    Native,
+    /// The pandoc AST is written as json without pandoc
+    Json,
+    /// The pandoc AST is printed as json instead of written to a file
+    Stdout,
+}
+
+/// The extensions of the files that are converted from a pandoc format
+fn source_extensions(from: &str) -> Vec<String> {
+    // Extensions of the format like `markdown+smart` don't change the files
+    let format = from.split(['+', '-']).next().unwrap_or(from);
+    let extensions: &[&str] = match format {
+        "markdown" | "markdown_strict" | "markdown_mmd" | "markdown_phpextra" | "gfm"
+        | "commonmark" | "commonmark_x" => &["md", "markdown"],
+        "html" => &["html", "htm"],
+        "latex" => &["tex"],
+        "mediawiki" | "dokuwiki" | "vimwiki" => &["wiki"],
+        "textile" => &["textile"],
+        format => return vec![format.to_owned()],
+    };
+    extensions
+        .iter()
+        .map(|&extension| extension.to_owned())
+        .collect()
+}
+
+fn convert_file(file: &Path, output_file: &Path, settings: &Settings) -> bool {
+    match &settings.from {
+        Some(from) => write_norg_file(file, output_file, from, settings),
+        None => parse_file(file, output_file, settings),
     }
 }
 
-fn parse_file(
-    file: &Path,
-    to: &str,
-    pandoc_args: Option<&str>,
-    output_file: &Path,
-    api_version: Vec<u32>,
-    workspace_root: &Path,
-) {
-    if !output_file.parent().unwrap().exists() {
+/// Converts a single file, returns whether that worked.
+///
+/// Errors in the file are reported, so the other files can still be converted
+fn parse_file(file: &Path, output_file: &Path, settings: &Settings) -> bool {
+    let name = display_name(file);
+    if settings.writer != Writer::Stdout
+        && !is_standard_stream(output_file)
+        && !output_file.parent().unwrap().exists()
+    {
         if let Err(e) = fs::create_dir_all(output_file.parent().unwrap()) {
             eprintln!(
                 "Error creating directory {}: {e}",
@@ -158,56 +655,357 @@ fn parse_file(
         }
     }
 
-    let file = fs::read_to_string(file).expect("Cannot read file");
+    let content = match read_input(file) {
+        Ok(content) => content,
+        Err(error) => {
+            eprintln!("{name}: error: couldn't read file: {error}");
+            return false;
+        }
+    };
+
+    let parsed = CONVERTER.with(|converter| {
+        let mut converter = converter.borrow_mut();
+        let converter = match &mut *converter {
+            Some(converter) => converter,
+            None => converter.insert(Converter::new(settings.options.clone())?),
+        };
+        if is_standard_stream(file) {
+            converter.parse(&content)
+        } else {
+            converter.parse_named(&file.to_string_lossy(), &content)
+        }
+    });
+    let mut parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!(
+                "{}:{}:{}: error: {} ({})",
+                name, error.line, error.column, error.message, error.node_kind
+            );
+            return false;
+        }
+    };
 
-    let ast = norg_pandoc_ast::parse(&file, to, api_version, workspace_root);
+    // Recorded before anything can fail, so files with broken links are converted again once
+    // their targets change
+    if !is_standard_stream(file) {
+        let directory = file.parent().unwrap();
+        let dependencies = parsed
+            .links
+            .files
+            .iter()
+            .filter(|link| !link.path.is_empty())
+            .map(|link| {
+                let mut path = if link.path.starts_with('$') {
+                    PathBuf::from(&link.resolved)
+                } else {
+                    directory.join(&link.resolved)
+                }
+                .into_os_string();
+                path.push(".norg");
+                normalize(Path::new(&path))
+            })
+            .collect();
+        settings
+            .dependencies
+            .lock()
+            .unwrap()
+            .insert(normalize(file), dependencies);
+    }
 
-    let mut pandoc_command = Command::new(PANDOC_PATH.unwrap_or("pandoc"));
+    let mut has_errors = false;
+    for mut diagnostic in parsed.diagnostics {
+        if settings.deny_warnings {
+            diagnostic.severity = Severity::Error;
+        }
+        has_errors |= diagnostic.severity == Severity::Error;
+        if diagnostic.span.is_some() {
+            eprintln!("{name}:{diagnostic}");
+        } else {
+            eprintln!("{name}: {diagnostic}");
+        }
+    }
+    // Broken links are only in the link report, not in the diagnostics
+    let deny_broken_links = settings.deny_broken_links || settings.deny_warnings;
+    let severity = if deny_broken_links {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+    for link in &parsed.links.broken {
+        eprintln!(
+            "{name}:{}:{}: {severity}: couldn't find the link target {}",
+            link.span.line, link.span.column, link.target
+        );
+        has_errors |= deny_broken_links;
+    }
+    if has_errors {
+        return false;
+    }
 
-    if let Some(arg) = pandoc_args {
-        pandoc_command.arg(arg);
+    for (key, value) in &settings.metadata {
+        parsed
+            .ast
+            .meta
+            .entry(key.clone())
+            .or_insert_with(|| value.clone());
     }
-    let mut pandoc_command = pandoc_command
+
+    let rendered = match settings.writer {
+        Writer::Pandoc => None,
+        Writer::Native => Some(render_html(&parsed.ast)),
+        Writer::Json => Some(parsed.ast.to_json()),
+        Writer::Stdout => {
+            println!("{}", parsed.ast.to_json());
+            return true;
+        }
+    };
+    if let Some(rendered) = rendered {
+        return match write_output(output_file, rendered) {
+            Ok(()) => true,
+            Err(error) => {
+                eprintln!(
+                    "{}: error: couldn't write file: {error}",
+                    output_file.display()
+                );
+                false
+            }
+        };
+    }
+
+    let mut pandoc_command = Command::new(&settings.pandoc);
+
+    pandoc_command.args(&settings.pandoc_args);
+    if let Some(division) = &settings.top_level_division {
+        pandoc_command.arg(format!("--top-level-division={division}"));
+    }
+    pandoc_command
         .arg("--from=json")
+        .arg(format!("--to={}", settings.to))
         .arg("-o")
         .arg(output_file)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't spawn pandoc");
-    let mut stdin = pandoc_command.stdin.take().unwrap();
-    stdin
-        .write_all(ast.to_json().as_bytes())
-        .expect("Couldnt write to pandocs stdin");
-    stdin.flush().unwrap();
-    drop(stdin);
-    match pandoc_command.wait_with_output() {
+        .stdout(if is_standard_stream(output_file) {
+            // Binary formats would be mangled by printing them as text
+            Stdio::inherit()
+        } else {
+            Stdio::piped()
+        });
+    match run_pandoc(&mut pandoc_command, parsed.ast.to_json().as_bytes()) {
         Ok(output) => {
             print!(
                 "{}{}",
                 String::from_utf8_lossy(&output.stdout),
                 String::from_utf8_lossy(&output.stderr)
-            )
+            );
+            output.status.success()
+        }
+        Err(error) => {
+            eprintln!("{name}: error: couldn't run pandoc: {error}");
+            false
         }
-        Err(error) => eprintln!("Couldn't run pandoc: {error}"),
     }
 }
 
-fn get_api_version() -> Vec<u32> {
-    let mut pandoc_command = Command::new(PANDOC_PATH.unwrap_or("pandoc"))
+/// Runs pandoc with the input on its stdin and waits for it to finish
+fn run_pandoc(pandoc_command: &mut Command, input: &[u8]) -> io::Result<Output> {
+    let mut pandoc = pandoc_command.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = pandoc.stdin.take() {
+        stdin.write_all(input)?;
+        stdin.flush()?;
+    }
+    pandoc.wait_with_output()
+}
+
+/// Lets pandoc read a file in another format and writes it as norg, returns whether that worked
+fn write_norg_file(file: &Path, output_file: &Path, from: &str, settings: &Settings) -> bool {
+    let name = display_name(file);
+    if !is_standard_stream(output_file) {
+        if let Err(e) = fs::create_dir_all(output_file.parent().unwrap()) {
+            eprintln!(
+                "Error creating directory {}: {e}",
+                output_file.parent().unwrap().display()
+            );
+            exit(3);
+        }
+    }
+
+    let mut pandoc_command = Command::new(&settings.pandoc);
+    pandoc_command.args(&settings.pandoc_args);
+    pandoc_command
+        .arg(format!("--from={from}"))
+        .arg("--to=json");
+    // Without a file pandoc reads stdin, which it has to inherit for that
+    if is_standard_stream(file) {
+        pandoc_command.stdin(Stdio::inherit());
+    } else {
+        pandoc_command.arg(file);
+    }
+    let output = pandoc_command.output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            eprintln!(
+                "{}: error: pandoc couldn't read the file: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return false;
+        }
+        Err(error) => {
+            eprintln!("Couldn't run pandoc: {error}");
+            return false;
+        }
+    };
+
+    let ast = pandoc_ast::Pandoc::from_json(&String::from_utf8_lossy(&output.stdout));
+    let written = write_norg(&ast);
+
+    let mut has_errors = false;
+    for mut diagnostic in written.diagnostics {
+        if settings.deny_warnings {
+            diagnostic.severity = Severity::Error;
+        }
+        has_errors |= diagnostic.severity == Severity::Error;
+        eprintln!("{name}: {diagnostic}");
+    }
+    if has_errors {
+        return false;
+    }
+
+    match write_output(output_file, written.source) {
+        Ok(()) => true,
+        Err(error) => {
+            eprintln!(
+                "{}: error: couldn't write file: {error}",
+                output_file.display()
+            );
+            false
+        }
+    }
+}
+
+/// Makes a path absolute and removes `.` and `..` without touching the file system, so it works
+/// for deleted files too
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = env::current_dir().unwrap_or_default();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Whether a path given on the command line is `-`, meaning stdin or stdout
+fn is_standard_stream(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// The name a file is reported with, stdin doesn't have one
+fn display_name(file: &Path) -> String {
+    if is_standard_stream(file) {
+        String::from("<stdin>")
+    } else {
+        file.display().to_string()
+    }
+}
+
+fn read_input(file: &Path) -> io::Result<String> {
+    if is_standard_stream(file) {
+        io::read_to_string(io::stdin())
+    } else {
+        fs::read_to_string(file)
+    }
+}
+
+fn write_output(output_file: &Path, content: String) -> io::Result<()> {
+    if is_standard_stream(output_file) {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()
+    } else {
+        fs::write(output_file, content)
+    }
+}
+
+/// Round trips every norg file in the input and prints the differences, returns whether there
+/// were none
+fn round_trip(input: &Path) -> bool {
+    let mut converter = match Converter::new(ParseOptions::new()) {
+        Ok(converter) => converter,
+        Err(error) => {
+            eprintln!("error: {}", error.message);
+            return false;
+        }
+    };
+
+    let mut unchanged = true;
+    for entry in WalkDir::new(input) {
+        let file = entry.unwrap().path().to_path_buf();
+        if !file.is_file() || file.extension().map_or(true, |e| e != "norg") {
+            continue;
+        }
+
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(error) => {
+                eprintln!("{}: error: couldn't read file: {error}", file.display());
+                unchanged = false;
+                continue;
+            }
+        };
+        let round_trip = match converter.round_trip(&content) {
+            Ok(round_trip) => round_trip,
+            Err(error) => {
+                eprintln!(
+                    "{}:{}:{}: error: {} ({})",
+                    file.display(),
+                    error.line,
+                    error.column,
+                    error.message,
+                    error.node_kind
+                );
+                unchanged = false;
+                continue;
+            }
+        };
+
+        for difference in &round_trip.differences {
+            if difference.span.is_some() {
+                println!("{}:{difference}", file.display());
+            } else {
+                println!("{}: {difference}", file.display());
+            }
+        }
+        unchanged &= round_trip.differences.is_empty();
+    }
+
+    unchanged
+}
+
+/// The first line of `pandoc --version`, empty if pandoc can't be run
+fn get_pandoc_version(pandoc: &str) -> String {
+    match Command::new(pandoc).arg("--version").output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned(),
+        Err(_) => String::new(),
+    }
+}
+
+fn get_api_version(pandoc: &str) -> Vec<u32> {
+    let mut pandoc_command = Command::new(pandoc);
+    pandoc_command
         .arg("--from=gfm")
         .arg("--to=json")
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .spawn()
-        .expect("Couldn't spawn pandoc");
-    let mut stdin = pandoc_command.stdin.take().unwrap();
-    stdin
-        .write_all("t".as_bytes())
-        .expect("Couldnt write to pandocs stdin");
-    stdin.flush().unwrap();
-    drop(stdin);
-    match pandoc_command.wait_with_output() {
+        .stdout(Stdio::piped());
+    match run_pandoc(&mut pandoc_command, b"t") {
         Ok(output) => {
             pandoc_ast::Pandoc::from_json(&String::from_utf8_lossy(&output.stdout))
                 .pandoc_api_version
@@ -0,0 +1,111 @@
+//! Converts the files of a directory again whenever they change
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+
+use crate::{convert_file, normalize, output_file, Settings, Sources};
+
+/// How long to wait for more changes before converting, editors often save a file in several
+/// steps
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the input directory and converts the files that changed, together with the files
+/// linking to them.
+///
+/// Errors in single files are reported and the watching goes on, it only returns when the
+/// watcher itself fails
+pub fn watch(input: &Path, output: &Path, sources: &Sources, settings: &Settings) {
+    let root = normalize(input);
+    let (sender, receiver) = mpsc::channel();
+    let mut debouncer = match new_debouncer(DEBOUNCE, sender) {
+        Ok(debouncer) => debouncer,
+        Err(error) => {
+            eprintln!("Couldn't start watching: {error}");
+            return;
+        }
+    };
+    if let Err(error) = debouncer.watcher().watch(&root, RecursiveMode::Recursive) {
+        eprintln!("Couldn't watch {}: {error}", input.display());
+        return;
+    }
+    eprintln!("Watching {} for changes", input.display());
+
+    for events in receiver {
+        let events = match events {
+            Ok(events) => events,
+            Err(error) => {
+                eprintln!("error: watching failed: {error}");
+                continue;
+            }
+        };
+
+        // The paths relative to the input directory, a batch can have a file more than once
+        let changed: BTreeSet<PathBuf> = events
+            .into_iter()
+            .filter_map(|event| {
+                let file = event.path.strip_prefix(&root).ok()?;
+                sources.contains(file).then(|| file.to_path_buf())
+            })
+            .collect();
+        if changed.is_empty() {
+            continue;
+        }
+
+        let mut convert = BTreeSet::new();
+        for file in &changed {
+            if input.join(file).is_file() {
+                convert.insert(file.clone());
+            } else {
+                remove(input, output, file, settings);
+            }
+        }
+
+        // The lock has to be gone before converting, that records the dependencies again
+        {
+            let changed: Vec<PathBuf> = changed.iter().map(|file| root.join(file)).collect();
+            let dependencies = settings.dependencies.lock().unwrap();
+            for (file, targets) in dependencies.iter() {
+                if !targets.iter().any(|target| changed.contains(target)) {
+                    continue;
+                }
+                if let Ok(file) = file.strip_prefix(&root) {
+                    if input.join(file).is_file() && sources.contains(file) {
+                        convert.insert(file.to_path_buf());
+                    }
+                }
+            }
+        }
+
+        for file in convert {
+            let output = output_file(output, &file, &settings.to);
+            let file = input.join(file);
+            if convert_file(&file, &output, settings) {
+                eprintln!("{}: converted", file.display());
+            }
+        }
+    }
+}
+
+/// Removes the output of a deleted file
+fn remove(input: &Path, output: &Path, file: &Path, settings: &Settings) {
+    settings
+        .dependencies
+        .lock()
+        .unwrap()
+        .remove(&normalize(&input.join(file)));
+
+    let output = output_file(output, file, &settings.to);
+    match fs::remove_file(&output) {
+        Ok(()) => eprintln!("{}: removed", output.display()),
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => eprintln!("{}: error: couldn't remove file: {error}", output.display()),
+    }
+}